@@ -0,0 +1,255 @@
+/// This file clips vent lines to a rectangular window, so that only
+/// the part of a line inside the window has to be counted.
+///
+/// Horizontal, vertical and 45 degree lines visit a lattice point at
+/// every step, so clipping amounts to limiting the range of steps on
+/// each axis, like Cohen-Sutherland or Liang-Barsky do for real lines.
+/// The clipped line covers exactly the points of the original line
+/// that lie inside the window.
+///
+/// Lines of other slopes cannot be clipped without changing their
+/// points, but they advance their major axis on every step, so only
+/// the steps within the window on that axis have to be walked.
+use crate::common::{Bounds, Coordinate, LineMode};
+use crate::error::SlopeError;
+
+/// Returns the range of steps `t` for which `start + t * step` lies
+/// within `min..=max`, for a step of -1, 0 or 1. The steps are computed
+/// in i128, since the window and the line may be far apart.
+fn step_range(start: i64, step: i64, min: i64, max: i64) -> Option<(i128, i128)> {
+    let (start, min, max) = (start as i128, min as i128, max as i128);
+    match step {
+        0 => (min..=max).contains(&start).then_some((0, i128::MAX)),
+        1 => Some((min - start, max - start)),
+        _ => Some((start - max, start - min)),
+    }
+}
+
+/// Returns the direction of each axis of the line, as -1, 0 or 1.
+fn direction(start: &Coordinate, end: &Coordinate) -> (i64, i64) {
+    (end.x.cmp(&start.x) as i64, end.y.cmp(&start.y) as i64)
+}
+
+/// Clips a horizontal, vertical or 45 degree line to the window,
+/// keeping its direction. Returns None if no point of the line lies
+/// inside the window.
+///
+/// Returns a SlopeError if the line has any other slope, since
+/// clipping it would change the points chosen by the Bresenham
+/// rasterizer; see `clip_steps` for those lines.
+pub fn clip_line(
+    start: Coordinate,
+    end: Coordinate,
+    window: &Bounds,
+) -> Result<Option<(Coordinate, Coordinate)>, SlopeError> {
+    if !LineMode::WithDiagonals.accepts(&start, &end) {
+        return Err(SlopeError);
+    }
+    let (dx, dy) = direction(&start, &end);
+    let steps = start.chebyshev_distance(&end) as i128;
+    let Some((x_from, x_to)) = step_range(start.x, dx, window.min.x, window.max.x) else {
+        return Ok(None);
+    };
+    let Some((y_from, y_to)) = step_range(start.y, dy, window.min.y, window.max.y) else {
+        return Ok(None);
+    };
+    let (from, to) = (x_from.max(y_from).max(0), x_to.min(y_to).min(steps));
+    if from > to {
+        return Ok(None);
+    }
+    // Both steps lie on the line, so the points fit in i64.
+    let at = |t: i128| Coordinate {
+        x: (start.x as i128 + t * dx as i128) as i64,
+        y: (start.y as i128 + t * dy as i128) as i64,
+    };
+    Ok(Some((at(from), at(to))))
+}
+
+/// Returns the range of steps, both included, during which a line of
+/// any slope stays within the window along its major axis, or None if
+/// the line cannot enter the window. Every step of a line advances its
+/// major axis, so the points of the line inside the window are among
+/// those steps, which can be walked with `LineIterator::from_step`.
+pub fn clip_steps(start: Coordinate, end: Coordinate, window: &Bounds) -> Option<(u64, u64)> {
+    Bounds::new(start, end).intersection(window)?;
+    let (dx, dy) = direction(&start, &end);
+    let steps = start.chebyshev_distance(&end);
+    let (from, to) = if start.x.abs_diff(end.x) >= start.y.abs_diff(end.y) {
+        step_range(start.x, dx, window.min.x, window.max.x)?
+    } else {
+        step_range(start.y, dy, window.min.y, window.max.y)?
+    };
+    let (from, to) = (from.max(0), to.min(steps as i128));
+    (from <= to).then_some((from as u64, to as u64))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::line::LineIterator;
+
+    fn window() -> Bounds {
+        Bounds::new(Coordinate { x: 2, y: 2 }, Coordinate { x: 6, y: 5 })
+    }
+
+    #[test]
+    fn test_clip_line_axis_aligned() {
+        let clipped = clip_line(
+            Coordinate { x: 0, y: 3 },
+            Coordinate { x: 9, y: 3 },
+            &window(),
+        );
+        assert_eq!(
+            clipped,
+            Ok(Some((Coordinate { x: 2, y: 3 }, Coordinate { x: 6, y: 3 })))
+        );
+
+        let clipped = clip_line(
+            Coordinate { x: 4, y: 9 },
+            Coordinate { x: 4, y: 4 },
+            &window(),
+        );
+        assert_eq!(
+            clipped,
+            Ok(Some((Coordinate { x: 4, y: 5 }, Coordinate { x: 4, y: 4 })))
+        );
+
+        let outside = clip_line(
+            Coordinate { x: 0, y: 0 },
+            Coordinate { x: 9, y: 0 },
+            &window(),
+        );
+        assert_eq!(outside, Ok(None));
+    }
+
+    #[test]
+    fn test_clip_line_diagonal() {
+        let clipped = clip_line(
+            Coordinate { x: 8, y: 0 },
+            Coordinate { x: 0, y: 8 },
+            &window(),
+        );
+        assert_eq!(
+            clipped,
+            Ok(Some((Coordinate { x: 6, y: 2 }, Coordinate { x: 3, y: 5 })))
+        );
+
+        // Passes the corner of the window without entering it.
+        let outside = clip_line(
+            Coordinate { x: 0, y: 3 },
+            Coordinate { x: 3, y: 0 },
+            &window(),
+        );
+        assert_eq!(outside, Ok(None));
+    }
+
+    #[test]
+    fn test_clip_line_unsupported_slope() {
+        let clipped = clip_line(
+            Coordinate { x: 0, y: 0 },
+            Coordinate { x: 4, y: 2 },
+            &window(),
+        );
+        assert_eq!(clipped, Err(SlopeError));
+    }
+
+    #[test]
+    fn test_clip_line_far_from_window() {
+        let window = Bounds::new(
+            Coordinate {
+                x: i64::MIN,
+                y: i64::MIN,
+            },
+            Coordinate {
+                x: i64::MIN + 9,
+                y: i64::MIN + 9,
+            },
+        );
+        let far = Coordinate {
+            x: i64::MAX - 5,
+            y: i64::MAX - 5,
+        };
+        assert_eq!(
+            clip_line(
+                far,
+                Coordinate {
+                    x: i64::MAX,
+                    y: i64::MAX
+                },
+                &window
+            ),
+            Ok(None)
+        );
+        assert_eq!(
+            clip_line(
+                Coordinate {
+                    x: i64::MAX - 5,
+                    y: i64::MIN + 3
+                },
+                far,
+                &window
+            ),
+            Ok(None)
+        );
+        let row = |x| Coordinate { x, y: i64::MIN + 3 };
+        assert_eq!(
+            clip_line(row(i64::MAX), row(i64::MIN), &window),
+            Ok(Some((row(i64::MIN + 9), row(i64::MIN))))
+        );
+        assert_eq!(
+            clip_steps(far, Coordinate { x: i64::MAX, y: 0 }, &window),
+            None
+        );
+    }
+
+    #[test]
+    fn test_clip_steps() {
+        let window = window();
+        for (start, end) in [
+            (Coordinate { x: -3, y: 1 }, Coordinate { x: 12, y: 6 }),
+            (Coordinate { x: 4, y: 9 }, Coordinate { x: 3, y: -6 }),
+            (Coordinate { x: 0, y: 0 }, Coordinate { x: 1, y: 9 }),
+            (Coordinate { x: 20, y: 0 }, Coordinate { x: 30, y: 7 }),
+        ] {
+            let expected: Vec<Coordinate> = LineIterator::new(start, end)
+                .filter(|point| window.contains(point))
+                .collect();
+            let clipped: Vec<Coordinate> = match clip_steps(start, end, &window) {
+                Some((from, to)) => LineIterator::from_step(start, end, from)
+                    .take((to - from + 1) as usize)
+                    .filter(|point| window.contains(point))
+                    .collect(),
+                None => Vec::new(),
+            };
+            assert_eq!(clipped, expected);
+        }
+
+        // A line crossing a small window far from its ends.
+        let (start, end) = (
+            Coordinate { x: -1 << 40, y: 0 },
+            Coordinate { x: 1 << 40, y: 3 },
+        );
+        let (from, to) = clip_steps(start, end, &window).unwrap();
+        assert_eq!((from, to), ((1 << 40) + 2, (1 << 40) + 6));
+    }
+
+    #[test]
+    fn test_clip_line_keeps_points_inside() {
+        let window = window();
+        for (start, end) in [
+            (Coordinate { x: -3, y: 10 }, Coordinate { x: 10, y: -3 }),
+            (Coordinate { x: 1, y: 1 }, Coordinate { x: 7, y: 7 }),
+            (Coordinate { x: 5, y: 4 }, Coordinate { x: 5, y: 4 }),
+        ] {
+            let expected: Vec<Coordinate> = LineIterator::new(start, end)
+                .filter(|point| window.contains(point))
+                .collect();
+            let clipped: Vec<Coordinate> = match clip_line(start, end, &window) {
+                Ok(Some((start, end))) => LineIterator::new(start, end).collect(),
+                Ok(None) => Vec::new(),
+                Err(kind) => panic!("unexpected error: {:?}", kind),
+            };
+            assert_eq!(clipped, expected);
+        }
+    }
+}
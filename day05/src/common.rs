@@ -1,24 +1,267 @@
 use std::fmt;
+use std::ops::{Add, AddAssign, Sub};
 use std::str::FromStr;
 
-#[derive(Debug, PartialEq, Clone, Copy)]
+use crate::error::{ColumnError, ParseErrorKind};
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Coordinate {
-    pub y: usize,
-    pub x: usize,
+    pub y: i64,
+    pub x: i64,
+}
+
+/// Parses a number surrounded by optional whitespace, reporting
+/// the column the number starts at when it is not a valid integer.
+pub(crate) fn parse_number(s: &str) -> Result<i64, ColumnError> {
+    let column = s.len() - s.trim_start().len() + 1;
+    s.trim().parse().map_err(|err| ColumnError {
+        column,
+        kind: ParseErrorKind::BadInteger(err),
+    })
 }
 
 impl FromStr for Coordinate {
-    type Err = std::num::ParseIntError;
+    type Err = ColumnError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let parts: Vec<&str> = s.trim().split(',').collect();
-        let col = parts[0].parse()?;
-        let row = parts[1].parse()?;
+        let (col, row) = s.split_once(',').ok_or(ColumnError {
+            column: s.len() - s.trim_start().len() + 1,
+            kind: ParseErrorKind::MissingComma,
+        })?;
+        let col = parse_number(col)?;
+        let row = parse_number(row).map_err(|err| err.shifted(s.len() - row.len()))?;
         Ok(Coordinate { x: col, y: row })
     }
 }
 
+/// A displacement between two coordinates, such as one step
+/// along a line or towards a neighbour.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub struct Vector {
+    pub dx: i64,
+    pub dy: i64,
+}
+
+impl Vector {
+    pub const UP: Vector = Vector { dx: 0, dy: -1 };
+    pub const DOWN: Vector = Vector { dx: 0, dy: 1 };
+    pub const LEFT: Vector = Vector { dx: -1, dy: 0 };
+    pub const RIGHT: Vector = Vector { dx: 1, dy: 0 };
+
+    /// The four horizontal and vertical unit steps.
+    pub const ORTHOGONAL: [Vector; 4] = [Vector::UP, Vector::RIGHT, Vector::DOWN, Vector::LEFT];
+
+    /// The eight unit steps including the diagonals, clockwise from up.
+    pub const ALL: [Vector; 8] = [
+        Vector::UP,
+        Vector { dx: 1, dy: -1 },
+        Vector::RIGHT,
+        Vector { dx: 1, dy: 1 },
+        Vector::DOWN,
+        Vector { dx: -1, dy: 1 },
+        Vector::LEFT,
+        Vector { dx: -1, dy: -1 },
+    ];
+
+    /// The unit step pointing in the same direction on each axis.
+    pub fn signum(self) -> Vector {
+        Vector {
+            dx: self.dx.signum(),
+            dy: self.dy.signum(),
+        }
+    }
+}
+
+impl Add<Vector> for Coordinate {
+    type Output = Coordinate;
+
+    fn add(self, vector: Vector) -> Coordinate {
+        Coordinate {
+            x: self.x + vector.dx,
+            y: self.y + vector.dy,
+        }
+    }
+}
+
+impl AddAssign<Vector> for Coordinate {
+    fn add_assign(&mut self, vector: Vector) {
+        *self = *self + vector;
+    }
+}
+
+impl Sub<Vector> for Coordinate {
+    type Output = Coordinate;
+
+    fn sub(self, vector: Vector) -> Coordinate {
+        Coordinate {
+            x: self.x - vector.dx,
+            y: self.y - vector.dy,
+        }
+    }
+}
+
+impl Sub<Coordinate> for Coordinate {
+    type Output = Vector;
+
+    fn sub(self, other: Coordinate) -> Vector {
+        Vector {
+            dx: self.x - other.x,
+            dy: self.y - other.y,
+        }
+    }
+}
+
+impl Coordinate {
+    /// Saturates at u64::MAX for points at opposite ends of the i64 range.
+    pub fn manhattan_distance(&self, other: &Coordinate) -> u64 {
+        self.x
+            .abs_diff(other.x)
+            .saturating_add(self.y.abs_diff(other.y))
+    }
+
+    pub fn chebyshev_distance(&self, other: &Coordinate) -> u64 {
+        self.x.abs_diff(other.x).max(self.y.abs_diff(other.y))
+    }
+
+    /// Steps by the vector, or returns None instead of overflowing.
+    pub fn checked_step(&self, vector: Vector) -> Option<Coordinate> {
+        Some(Coordinate {
+            x: self.x.checked_add(vector.dx)?,
+            y: self.y.checked_add(vector.dy)?,
+        })
+    }
+
+    /// Steps by the vector, or returns None when the step would
+    /// leave the bounds.
+    pub fn step_within(&self, vector: Vector, bounds: &Bounds) -> Option<Coordinate> {
+        self.checked_step(vector)
+            .filter(|coordinate| bounds.contains(coordinate))
+    }
+
+    /// The up to four horizontal and vertical neighbours within the bounds.
+    pub fn neighbours4<'a>(&self, bounds: &'a Bounds) -> impl Iterator<Item = Coordinate> + 'a {
+        let coordinate = *self;
+        Vector::ORTHOGONAL
+            .into_iter()
+            .filter_map(move |vector| coordinate.step_within(vector, bounds))
+    }
+
+    /// The up to eight neighbours, including diagonals, within the bounds.
+    pub fn neighbours8<'a>(&self, bounds: &'a Bounds) -> impl Iterator<Item = Coordinate> + 'a {
+        let coordinate = *self;
+        Vector::ALL
+            .into_iter()
+            .filter_map(move |vector| coordinate.step_within(vector, bounds))
+    }
+}
+
+/// A rectangle of coordinates, both corners included.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Bounds {
+    pub min: Coordinate,
+    pub max: Coordinate,
+}
+
+impl Bounds {
+    /// Creates the bounds spanned by two opposite corners.
+    pub fn new(corner: Coordinate, opposite: Coordinate) -> Self {
+        Self {
+            min: Coordinate {
+                x: corner.x.min(opposite.x),
+                y: corner.y.min(opposite.y),
+            },
+            max: Coordinate {
+                x: corner.x.max(opposite.x),
+                y: corner.y.max(opposite.y),
+            },
+        }
+    }
+
+    pub fn contains(&self, coordinate: &Coordinate) -> bool {
+        (self.min.x..=self.max.x).contains(&coordinate.x)
+            && (self.min.y..=self.max.y).contains(&coordinate.y)
+    }
+
+    /// Returns the smallest bounds containing both bounds.
+    pub fn union(&self, other: &Bounds) -> Bounds {
+        Bounds {
+            min: Coordinate {
+                x: self.min.x.min(other.min.x),
+                y: self.min.y.min(other.min.y),
+            },
+            max: Coordinate {
+                x: self.max.x.max(other.max.x),
+                y: self.max.y.max(other.max.y),
+            },
+        }
+    }
+
+    /// Returns the overlapping part of both bounds, if any.
+    pub fn intersection(&self, other: &Bounds) -> Option<Bounds> {
+        let bounds = Bounds {
+            min: Coordinate {
+                x: self.min.x.max(other.min.x),
+                y: self.min.y.max(other.min.y),
+            },
+            max: Coordinate {
+                x: self.max.x.min(other.max.x),
+                y: self.max.y.min(other.max.y),
+            },
+        };
+        (bounds.min.x <= bounds.max.x && bounds.min.y <= bounds.max.y).then_some(bounds)
+    }
+
+    /// The number of columns, saturating at u64::MAX for bounds
+    /// spanning the whole range of i64.
+    pub fn width(&self) -> u64 {
+        self.max.x.abs_diff(self.min.x).saturating_add(1)
+    }
+
+    /// The number of rows, saturating like `width`.
+    pub fn height(&self) -> u64 {
+        self.max.y.abs_diff(self.min.y).saturating_add(1)
+    }
+}
+
+/// The largest distance along an axis between the ends of a line.
+/// Keeping lines this short lets the differences between their ends,
+/// and the doubled error term of Bresenham's algorithm, fit in an i64.
+pub const MAX_SPAN: u64 = i64::MAX as u64 / 2;
+
+/// Returns true if the line from start to end is short enough to be
+/// rasterized, see MAX_SPAN.
+pub fn within_span(start: &Coordinate, end: &Coordinate) -> bool {
+    start.x.abs_diff(end.x) <= MAX_SPAN && start.y.abs_diff(end.y) <= MAX_SPAN
+}
+
+/// Which kinds of vent lines are taken into account.
+/// Part One only considers horizontal and vertical lines,
+/// Part Two also considers diagonal lines at exactly 45 degrees.
+/// AnySlope accepts every line, rasterizing other slopes with Bresenham.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum LineMode {
+    AxisAligned,
+    WithDiagonals,
+    AnySlope,
+}
+
+impl LineMode {
+    /// Returns true if the line from start to end is considered in this mode.
+    pub fn accepts(&self, start: &Coordinate, end: &Coordinate) -> bool {
+        let axis_aligned = start.x == end.x || start.y == end.y;
+        match self {
+            LineMode::AxisAligned => axis_aligned,
+            LineMode::WithDiagonals => {
+                axis_aligned || start.x.abs_diff(end.x) == start.y.abs_diff(end.y)
+            }
+            LineMode::AnySlope => true,
+        }
+    }
+}
+
 #[derive(PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Count(pub usize);
 
 impl fmt::Display for Count {
@@ -27,6 +270,7 @@ impl fmt::Display for Count {
     }
 }
 #[derive(PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct PointCount {
     pub point: Coordinate,
     pub count: Count,
@@ -41,3 +285,74 @@ impl fmt::Display for PointCount {
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_coordinate_arithmetic() {
+        let start = Coordinate { x: 2, y: 3 };
+        let end = Coordinate { x: -1, y: 7 };
+        assert_eq!(end - start, Vector { dx: -3, dy: 4 });
+        assert_eq!(start + (end - start), end);
+        assert_eq!(end - (end - start), start);
+        assert_eq!((end - start).signum(), Vector { dx: -1, dy: 1 });
+        assert_eq!(start.manhattan_distance(&end), 7);
+        assert_eq!(start.chebyshev_distance(&end), 4);
+    }
+
+    #[test]
+    fn test_coordinate_checked_step() {
+        let edge = Coordinate { x: i64::MAX, y: 0 };
+        assert_eq!(edge.checked_step(Vector::RIGHT), None);
+        assert_eq!(
+            edge.checked_step(Vector::LEFT),
+            Some(Coordinate {
+                x: i64::MAX - 1,
+                y: 0
+            })
+        );
+
+        let bounds = Bounds::new(Coordinate { x: 0, y: 0 }, Coordinate { x: 9, y: 9 });
+        let origin = Coordinate { x: 0, y: 0 };
+        assert_eq!(origin.step_within(Vector::UP, &bounds), None);
+        assert_eq!(
+            origin.step_within(Vector::DOWN, &bounds),
+            Some(Coordinate { x: 0, y: 1 })
+        );
+    }
+
+    #[test]
+    fn test_coordinate_neighbours() {
+        let bounds = Bounds::new(Coordinate { x: 0, y: 0 }, Coordinate { x: 9, y: 9 });
+        let corner = Coordinate { x: 0, y: 0 };
+        assert_eq!(
+            corner.neighbours4(&bounds).collect::<Vec<_>>(),
+            vec![Coordinate { x: 1, y: 0 }, Coordinate { x: 0, y: 1 }]
+        );
+        assert_eq!(corner.neighbours8(&bounds).count(), 3);
+        assert_eq!(Coordinate { x: 5, y: 5 }.neighbours4(&bounds).count(), 4);
+        assert_eq!(Coordinate { x: 5, y: 5 }.neighbours8(&bounds).count(), 8);
+    }
+
+    #[test]
+    fn test_bounds() {
+        let first = Bounds::new(Coordinate { x: 3, y: 0 }, Coordinate { x: 0, y: 2 });
+        let second = Bounds::new(Coordinate { x: 2, y: 1 }, Coordinate { x: 5, y: 5 });
+        assert_eq!((first.width(), first.height()), (4, 3));
+        assert_eq!(
+            first.union(&second),
+            Bounds::new(Coordinate { x: 0, y: 0 }, Coordinate { x: 5, y: 5 })
+        );
+        assert_eq!(
+            first.intersection(&second),
+            Some(Bounds::new(
+                Coordinate { x: 2, y: 1 },
+                Coordinate { x: 3, y: 2 }
+            ))
+        );
+        let far = Bounds::new(Coordinate { x: 10, y: 10 }, Coordinate { x: 11, y: 11 });
+        assert_eq!(first.intersection(&far), None);
+    }
+}
@@ -0,0 +1,122 @@
+/// This file defines the errors reported while reading vent lines.
+/// A ColumnError points at a column within a single line of text,
+/// a ParseError adds the file and line number it was found on,
+/// and a ReadError is either an I/O failure or a ParseError.
+/// A SlopeError comes from geometry rather than text, and is reported
+/// as UnsupportedSlope when it is found while parsing.
+use std::fmt;
+use std::io;
+use std::num::ParseIntError;
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum ParseErrorKind {
+    MissingArrow,
+    MissingComma,
+    BadInteger(ParseIntError),
+    UnsupportedSlope,
+    LineTooLong,
+}
+
+impl fmt::Display for ParseErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseErrorKind::MissingArrow => write!(f, "missing '->' between coordinates"),
+            ParseErrorKind::MissingComma => write!(f, "missing ',' between x and y"),
+            ParseErrorKind::BadInteger(err) => write!(f, "bad integer: {}", err),
+            ParseErrorKind::UnsupportedSlope => {
+                write!(f, "line is not horizontal, vertical or 45 degrees")
+            }
+            ParseErrorKind::LineTooLong => write!(
+                f,
+                "line spans more than {} along an axis",
+                crate::common::MAX_SPAN
+            ),
+        }
+    }
+}
+
+/// A line whose slope is not supported by a geometry routine,
+/// such as clipping or walking a 3D line.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct SlopeError;
+
+impl fmt::Display for SlopeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "unsupported slope")
+    }
+}
+
+impl std::error::Error for SlopeError {}
+
+impl From<SlopeError> for ParseErrorKind {
+    fn from(_: SlopeError) -> Self {
+        ParseErrorKind::UnsupportedSlope
+    }
+}
+
+/// An error at a 1-based column of a single line of text.
+#[derive(Debug, PartialEq, Clone)]
+pub struct ColumnError {
+    pub column: usize,
+    pub kind: ParseErrorKind,
+}
+
+impl ColumnError {
+    /// Moves the column right by `offset`, used when the text
+    /// that was parsed is a slice further into the line.
+    pub fn shifted(self, offset: usize) -> Self {
+        Self {
+            column: self.column + offset,
+            kind: self.kind,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct ParseError {
+    pub file: String,
+    pub line: usize,
+    pub column: usize,
+    pub kind: ParseErrorKind,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}:{}: {}",
+            self.file, self.line, self.column, self.kind
+        )
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+#[derive(Debug)]
+pub enum ReadError {
+    Io(io::Error),
+    Parse(ParseError),
+}
+
+impl fmt::Display for ReadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ReadError::Io(err) => write!(f, "{}", err),
+            ReadError::Parse(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl std::error::Error for ReadError {}
+
+impl From<io::Error> for ReadError {
+    fn from(err: io::Error) -> Self {
+        ReadError::Io(err)
+    }
+}
+
+impl From<ParseError> for ReadError {
+    fn from(err: ParseError) -> Self {
+        ReadError::Parse(err)
+    }
+}
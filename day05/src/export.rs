@@ -0,0 +1,124 @@
+/// This file writes results in formats other tools can consume.
+/// Overlap points are always available as CSV (`x,y,count`).
+/// With the `serde` feature, PointsData and lists of PointCount can
+/// also be written as JSON, and PointsData read back from JSON.
+use std::io::{self, Write};
+
+use crate::common::PointCount;
+
+/// Writes the points as CSV with a `x,y,count` header.
+pub fn write_points_csv<W: Write>(writer: &mut W, points: &[PointCount]) -> io::Result<()> {
+    writeln!(writer, "x,y,count")?;
+    for point_count in points {
+        writeln!(
+            writer,
+            "{},{},{}",
+            point_count.point.x, point_count.point.y, point_count.count.0
+        )?;
+    }
+    writer.flush()
+}
+
+#[cfg(feature = "serde")]
+mod json {
+    use std::io::{Read, Write};
+
+    use crate::common::PointCount;
+    use crate::file::PointsData;
+
+    /// Writes the points data as JSON.
+    pub fn write_points_data_json<W: Write>(
+        writer: W,
+        points: &PointsData,
+    ) -> serde_json::Result<()> {
+        serde_json::to_writer(writer, points)
+    }
+
+    /// Reads points data written by `write_points_data_json`.
+    /// The bounding box is recomputed from the point pairs, so it is
+    /// always consistent with them. Without a line number for every
+    /// pair, the pairs are numbered in order.
+    pub fn read_points_data_json<R: Read>(reader: R) -> serde_json::Result<PointsData> {
+        let points: PointsData = serde_json::from_reader(reader)?;
+        if points.line_numbers.len() == points.point_pairs.len() {
+            Ok(PointsData::from_numbered_pairs(
+                points.point_pairs,
+                points.line_numbers,
+            ))
+        } else {
+            Ok(PointsData::from_pairs(points.point_pairs))
+        }
+    }
+
+    /// Writes the points and their counts as a JSON array.
+    pub fn write_points_json<W: Write>(writer: W, points: &[PointCount]) -> serde_json::Result<()> {
+        serde_json::to_writer(writer, points)
+    }
+}
+
+#[cfg(feature = "serde")]
+pub use json::{read_points_data_json, write_points_data_json, write_points_json};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::{Coordinate, Count};
+
+    #[test]
+    fn test_write_points_csv() {
+        let points = vec![
+            PointCount {
+                point: Coordinate { x: 1, y: 2 },
+                count: Count(3),
+            },
+            PointCount {
+                point: Coordinate { x: -4, y: 0 },
+                count: Count(2),
+            },
+        ];
+        let mut output = Vec::new();
+        write_points_csv(&mut output, &points).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "x,y,count\n1,2,3\n-4,0,2\n"
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_points_data_json_round_trip() {
+        use crate::common::LineMode;
+        use crate::file::{read_file_to_points, PointsData};
+
+        let points = read_file_to_points("data/sample1.txt", LineMode::WithDiagonals).unwrap();
+        let mut output = Vec::new();
+        write_points_data_json(&mut output, &points).unwrap();
+        assert_eq!(read_points_data_json(output.as_slice()).unwrap(), points);
+
+        let json = r#"{"point_pairs":[[{"x":-1,"y":2},{"x":3,"y":2}]],
+            "min_x":0,"min_y":0,"max_x":0,"max_y":0}"#;
+        let points = read_points_data_json(json.as_bytes()).unwrap();
+        assert_eq!(
+            points,
+            PointsData::from_pairs(vec![(
+                Coordinate { x: -1, y: 2 },
+                Coordinate { x: 3, y: 2 }
+            )])
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_write_points_json() {
+        let points = vec![PointCount {
+            point: Coordinate { x: 1, y: 2 },
+            count: Count(3),
+        }];
+        let mut output = Vec::new();
+        write_points_json(&mut output, &points).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            r#"[{"point":{"y":2,"x":1},"count":3}]"#
+        );
+    }
+}
@@ -9,13 +9,26 @@
 /// these types and checking.
 use std::fs::File;
 use std::io::{self, BufRead, BufReader};
+use std::str::FromStr;
 
-use crate::common::Coordinate;
+use crate::common::{within_span, Bounds, Coordinate, LineMode};
+use crate::error::{ColumnError, ParseError, ParseErrorKind, ReadError};
 
+/// The path that stands for standard input on the command line.
+pub const STDIN_PATH: &str = "-";
+
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct PointsData {
     pub point_pairs: Vec<(Coordinate, Coordinate)>,
-    pub max_x: usize,
-    pub max_y: usize,
+    /// The 1-based input line each pair was read from, so pairs can be
+    /// traced back to the input even when lines were skipped.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub line_numbers: Vec<usize>,
+    pub min_x: i64,
+    pub min_y: i64,
+    pub max_x: i64,
+    pub max_y: i64,
 }
 
 pub struct PointsDataIter<'a> {
@@ -24,7 +37,7 @@ pub struct PointsDataIter<'a> {
 }
 
 impl PointsData {
-    pub fn iter(&self) -> PointsDataIter {
+    pub fn iter(&self) -> PointsDataIter<'_> {
         PointsDataIter {
             points_data: self,
             index: 0,
@@ -46,40 +59,191 @@ impl<'a> Iterator for PointsDataIter<'a> {
     }
 }
 
-fn parse_line(input: &str) -> Option<(Coordinate, Coordinate)> {
-    let coords: Vec<&str> = input.trim().split("->").collect();
-    if coords.len() != 2 {
-        return None;
+impl PointsData {
+    /// Creates PointsData from pairs of coordinates, computing their
+    /// bounding box. Without any pair the box is the origin alone.
+    /// The pairs are numbered as if they were the lines of an input.
+    pub fn from_pairs(point_pairs: Vec<(Coordinate, Coordinate)>) -> Self {
+        let line_numbers = (1..=point_pairs.len()).collect();
+        Self::from_numbered_pairs(point_pairs, line_numbers)
     }
-    let start: Coordinate = coords[0].parse().ok()?;
-    let end: Coordinate = coords[1].parse().ok()?;
 
-    if (start.x != end.x) && (start.y != end.y) {
-        return None;
+    /// Returns the input line the pair at `index` was read from, or its
+    /// position in the pairs counted from 1 when no line number is known.
+    pub fn line_number(&self, index: usize) -> usize {
+        self.line_numbers.get(index).copied().unwrap_or(index + 1)
     }
 
-    Some((start, end))
+    /// Creates PointsData from pairs of coordinates and the input line
+    /// each of them was read from.
+    pub fn from_numbered_pairs(
+        point_pairs: Vec<(Coordinate, Coordinate)>,
+        line_numbers: Vec<usize>,
+    ) -> Self {
+        let (min_x, min_y, max_x, max_y) = point_pairs
+            .iter()
+            .map(|&(start, end)| Bounds::new(start, end))
+            .reduce(|bounds, other| bounds.union(&other))
+            .map_or((0, 0, 0, 0), |bounds| {
+                (bounds.min.x, bounds.min.y, bounds.max.x, bounds.max.y)
+            });
+
+        PointsData {
+            point_pairs,
+            line_numbers,
+            min_x,
+            min_y,
+            max_x,
+            max_y,
+        }
+    }
 }
 
-pub fn read_file_to_points(path: &str) -> io::Result<PointsData> {
-    let file = File::open(path)?;
-    let reader = BufReader::new(file);
-    let point_pairs: Vec<_> = reader
-        .lines()
-        .filter_map(|line| line.ok().as_deref().and_then(parse_line))
-        .collect();
-
-    let (max_x, max_y) = point_pairs
-        .iter()
-        .fold((0, 0), |(max_x, max_y), (start, end)| {
-            (max_x.max(start.x).max(end.x), max_y.max(start.y).max(end.y))
+/// Parses the two coordinates on either side of the `->` arrow.
+pub(crate) fn parse_segment<T: FromStr<Err = ColumnError>>(
+    input: &str,
+) -> Result<(T, T), ColumnError> {
+    let (left, right) = input.split_once("->").ok_or(ColumnError {
+        column: input.trim_end().len() + 1,
+        kind: ParseErrorKind::MissingArrow,
+    })?;
+    let start: T = left.parse()?;
+    let end: T = right
+        .parse()
+        .map_err(|err: ColumnError| err.shifted(input.len() - right.len()))?;
+    Ok((start, end))
+}
+
+/// Parses a line like `0,9 -> 5,9`.
+/// Returns None for a valid line that is not considered in the given mode.
+fn parse_line(
+    input: &str,
+    mode: LineMode,
+) -> Result<Option<(Coordinate, Coordinate)>, ColumnError> {
+    let (start, end) = parse_segment::<Coordinate>(input)?;
+    let column = input.len() - input.trim_start().len() + 1;
+
+    if !within_span(&start, &end) {
+        return Err(ColumnError {
+            column,
+            kind: ParseErrorKind::LineTooLong,
+        });
+    }
+    if !mode.accepts(&start, &end) {
+        if mode == LineMode::AxisAligned {
+            return Ok(None);
+        }
+        return Err(ColumnError {
+            column,
+            kind: ParseErrorKind::UnsupportedSlope,
         });
+    }
+
+    Ok(Some((start, end)))
+}
+
+/// The values parsed from the lines of a reader, each with the number
+/// of its line, and the errors of the lines that could not be parsed.
+pub(crate) type ParsedLines<T> = (Vec<(usize, T)>, Vec<ParseError>);
+
+/// Parses every non-empty line of the reader with `parse`. In strict
+/// mode the first error is returned, otherwise all errors are collected
+/// next to the values that could be parsed. Every value comes with the
+/// 1-based number of the line it was parsed from.
+pub(crate) fn parse_lines_with<R, T, F>(
+    reader: R,
+    name: &str,
+    strict: bool,
+    parse: F,
+) -> Result<ParsedLines<T>, ReadError>
+where
+    R: BufRead,
+    F: Fn(&str) -> Result<Option<T>, ColumnError>,
+{
+    let mut values = Vec::new();
+    let mut errors = Vec::new();
+    for (index, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        match parse(&line) {
+            Ok(Some(value)) => values.push((index + 1, value)),
+            Ok(None) => {}
+            Err(err) => {
+                let err = ParseError {
+                    file: name.to_string(),
+                    line: index + 1,
+                    column: err.column,
+                    kind: err.kind,
+                };
+                if strict {
+                    return Err(err.into());
+                }
+                errors.push(err);
+            }
+        }
+    }
+    Ok((values, errors))
+}
+
+fn parse_lines<R: BufRead>(
+    reader: R,
+    name: &str,
+    mode: LineMode,
+    strict: bool,
+) -> Result<(PointsData, Vec<ParseError>), ReadError> {
+    let (numbered, errors) = parse_lines_with(reader, name, strict, |line| parse_line(line, mode))?;
+    let (line_numbers, point_pairs) = numbered.into_iter().unzip();
+    Ok((
+        PointsData::from_numbered_pairs(point_pairs, line_numbers),
+        errors,
+    ))
+}
+
+/// Reads the vent lines from any reader, stopping at the first line
+/// that cannot be parsed. The name is used as the file in errors.
+pub fn read_points<R: BufRead>(
+    reader: R,
+    name: &str,
+    mode: LineMode,
+) -> Result<PointsData, ReadError> {
+    let (points, _) = parse_lines(reader, name, mode, true)?;
+    Ok(points)
+}
 
-    Ok(PointsData {
-        point_pairs,
-        max_x,
-        max_y,
-    })
+/// Reads the vent lines from any reader, skipping lines that cannot be
+/// parsed and returning their errors alongside the parsed data.
+pub fn read_points_lenient<R: BufRead>(
+    reader: R,
+    name: &str,
+    mode: LineMode,
+) -> Result<(PointsData, Vec<ParseError>), ReadError> {
+    parse_lines(reader, name, mode, false)
+}
+
+/// Opens a file for reading, or standard input when the path is `-`.
+pub fn open_input(path: &str) -> io::Result<Box<dyn BufRead>> {
+    if path == STDIN_PATH {
+        Ok(Box::new(io::stdin().lock()))
+    } else {
+        Ok(Box::new(BufReader::new(File::open(path)?)))
+    }
+}
+
+/// Reads the vent lines from a file, stopping at the first line that
+/// cannot be parsed.
+pub fn read_file_to_points(path: &str, mode: LineMode) -> Result<PointsData, ReadError> {
+    read_points(BufReader::new(File::open(path)?), path, mode)
+}
+
+/// Reads the vent lines from a file, skipping lines that cannot be
+/// parsed and returning their errors alongside the parsed data.
+pub fn read_file_to_points_lenient(
+    path: &str,
+    mode: LineMode,
+) -> Result<(PointsData, Vec<ParseError>), ReadError> {
+    read_points_lenient(BufReader::new(File::open(path)?), path, mode)
 }
 
 #[cfg(test)]
@@ -112,18 +276,105 @@ mod tests {
     #[test]
     fn test_parse_line_ok() {
         let coord_str = String::from("0,9 -> 2,9");
-        let coords = parse_line(&coord_str);
+        let coords = parse_line(&coord_str, LineMode::AxisAligned);
         assert_eq!(
             coords.unwrap(),
-            (Coordinate { x: 0, y: 9 }, Coordinate { x: 2, y: 9 })
+            Some((Coordinate { x: 0, y: 9 }, Coordinate { x: 2, y: 9 }))
         );
     }
 
     #[test]
     fn test_parse_line_return_none() {
         let coord_str = String::from("0,9 -> 2,8"); // not horizontal or vertical
-        let coords = parse_line(&coord_str);
-        assert!(coords.is_none());
+        let coords = parse_line(&coord_str, LineMode::AxisAligned);
+        assert_eq!(coords, Ok(None));
+    }
+
+    #[test]
+    fn test_parse_line_diagonal() {
+        let coord_str = String::from("8,0 -> 0,8");
+        assert_eq!(parse_line(&coord_str, LineMode::AxisAligned), Ok(None));
+        assert_eq!(
+            parse_line(&coord_str, LineMode::WithDiagonals).unwrap(),
+            Some((Coordinate { x: 8, y: 0 }, Coordinate { x: 0, y: 8 }))
+        );
+
+        let coord_str = String::from("0,9 -> 2,8"); // not 45 degrees
+        assert_eq!(
+            parse_line(&coord_str, LineMode::WithDiagonals),
+            Err(ColumnError {
+                column: 1,
+                kind: ParseErrorKind::UnsupportedSlope
+            })
+        );
+        assert_eq!(
+            parse_line(&coord_str, LineMode::AnySlope),
+            Ok(Some((Coordinate { x: 0, y: 9 }, Coordinate { x: 2, y: 8 })))
+        );
+    }
+
+    #[test]
+    fn test_coordinate_from_str_errors() {
+        let err = "5".parse::<Coordinate>().unwrap_err();
+        assert_eq!(err.column, 1);
+        assert_eq!(err.kind, ParseErrorKind::MissingComma);
+
+        let err = " 5,x".parse::<Coordinate>().unwrap_err();
+        assert_eq!(err.column, 4);
+        assert!(matches!(err.kind, ParseErrorKind::BadInteger(_)));
+    }
+
+    #[test]
+    fn test_parse_line_errors() {
+        let err = parse_line("0,9 2,9", LineMode::AxisAligned).unwrap_err();
+        assert_eq!(err.column, 8);
+        assert_eq!(err.kind, ParseErrorKind::MissingArrow);
+
+        let err = parse_line("0,9 -> 2", LineMode::AxisAligned).unwrap_err();
+        assert_eq!(err.column, 8);
+        assert_eq!(err.kind, ParseErrorKind::MissingComma);
+
+        let err = parse_line("0,9 -> 2,y", LineMode::AxisAligned).unwrap_err();
+        assert_eq!(err.column, 10);
+        assert!(matches!(err.kind, ParseErrorKind::BadInteger(_)));
+
+        let err = parse_line(
+            " -9223372036854775808,0 -> 9223372036854775807,0",
+            LineMode::AxisAligned,
+        )
+        .unwrap_err();
+        assert_eq!(err.column, 2);
+        assert_eq!(err.kind, ParseErrorKind::LineTooLong);
+    }
+
+    #[test]
+    fn test_points_data_bounds_from_lines() {
+        let points = PointsData::from_pairs(vec![
+            (
+                Coordinate {
+                    x: 1_000_000,
+                    y: 1_000_002,
+                },
+                Coordinate {
+                    x: 1_000_003,
+                    y: 1_000_002,
+                },
+            ),
+            (
+                Coordinate {
+                    x: 1_000_001,
+                    y: 1_000_000,
+                },
+                Coordinate {
+                    x: 1_000_001,
+                    y: 1_000_001,
+                },
+            ),
+        ]);
+        assert_eq!(
+            (points.min_x, points.min_y, points.max_x, points.max_y),
+            (1_000_000, 1_000_000, 1_000_003, 1_000_002)
+        );
     }
 
     #[test]
@@ -133,7 +384,7 @@ mod tests {
         writeln!(file, "0,0 -> 0,1").unwrap();
         writeln!(file, "0,1 -> 1,1").unwrap();
 
-        let points_data = read_file_to_points(path).unwrap();
+        let points_data = read_file_to_points(path, LineMode::AxisAligned).unwrap();
         assert_eq!(points_data.point_pairs.len(), 2);
         assert_eq!(points_data.max_x, 1);
         assert_eq!(points_data.max_y, 1);
@@ -141,12 +392,83 @@ mod tests {
         let _ = std::fs::remove_file(path);
     }
 
+    #[test]
+    fn test_read_file_to_points_negative() {
+        let path = "/tmp/test_read_file_to_points_negative.txt";
+        let mut file = File::create(path).unwrap();
+        writeln!(file, "-3,2 -> 3,2").unwrap();
+        writeln!(file, "-1,-5 -> 1,-7").unwrap();
+
+        let points_data = read_file_to_points(path, LineMode::WithDiagonals).unwrap();
+        assert_eq!(points_data.point_pairs.len(), 2);
+        assert_eq!(points_data.min_x, -3);
+        assert_eq!(points_data.min_y, -7);
+        assert_eq!(points_data.max_x, 3);
+        assert_eq!(points_data.max_y, 2);
+
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn test_read_file_to_points_reports_line() {
+        let path = "/tmp/test_read_file_to_points_reports_line.txt";
+        let mut file = File::create(path).unwrap();
+        writeln!(file, "0,0 -> 0,1").unwrap();
+        writeln!(file, "0,1 -> 1").unwrap();
+        writeln!(file, "5").unwrap();
+
+        let err = read_file_to_points(path, LineMode::AxisAligned).unwrap_err();
+        match err {
+            ReadError::Parse(err) => {
+                assert_eq!(err.file, path);
+                assert_eq!(err.line, 2);
+                assert_eq!(err.column, 8);
+                assert_eq!(err.kind, ParseErrorKind::MissingComma);
+            }
+            ReadError::Io(err) => panic!("unexpected I/O error: {}", err),
+        }
+
+        let (points_data, errors) =
+            read_file_to_points_lenient(path, LineMode::AxisAligned).unwrap();
+        assert_eq!(points_data.point_pairs.len(), 1);
+        assert_eq!(errors.len(), 2);
+        assert_eq!(errors[1].line, 3);
+        assert_eq!(errors[1].kind, ParseErrorKind::MissingArrow);
+        assert_eq!(
+            errors[1].to_string(),
+            format!("{}:3:2: missing '->' between coordinates", path)
+        );
+
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn test_read_points_from_buffer() {
+        let input = "0,9 -> 5,9\n8,0 -> 0,8\n\n9,4 -> 3,4\n";
+        let points_data = read_points(input.as_bytes(), "buffer", LineMode::AxisAligned).unwrap();
+        assert_eq!(points_data.point_pairs.len(), 2);
+        assert_eq!(points_data.max_x, 9);
+        assert_eq!(points_data.max_y, 9);
+
+        let (points_data, errors) = read_points_lenient(
+            "1,1 -> 2\n1,1 -> 1,3".as_bytes(),
+            "buffer",
+            LineMode::AxisAligned,
+        )
+        .unwrap();
+        assert_eq!(points_data.point_pairs.len(), 1);
+        assert_eq!(
+            errors[0].to_string(),
+            "buffer:1:8: missing ',' between x and y"
+        );
+    }
+
     #[test]
     fn test_read_file_to_points_empty_file() {
         let path = "/tmp/test_read_file_to_points_empty_file.txt";
         let _ = File::create(path).unwrap();
 
-        let points_data = read_file_to_points(path).unwrap();
+        let points_data = read_file_to_points(path, LineMode::AxisAligned).unwrap();
         assert_eq!(points_data.point_pairs.len(), 0);
         assert_eq!(points_data.max_x, 0);
         assert_eq!(points_data.max_y, 0);
@@ -157,9 +479,9 @@ mod tests {
     #[test]
     fn test_read_file_to_points_nonexistent_file() {
         let path = "/tmp/nonexistent_file.txt";
-        assert!(Path::new(path).exists() == false);
+        assert!(!Path::new(path).exists());
 
-        let result = read_file_to_points(path);
+        let result = read_file_to_points(path, LineMode::AxisAligned);
         assert!(result.is_err());
 
         let _ = std::fs::remove_file(path);
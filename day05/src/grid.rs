@@ -1,69 +1,479 @@
 /// A Grid struct represents a grid/matrix.
 /// It provides methods to add lines and points to the grid,
 /// resize the grid, and keep count the number of occurances
-/// at any point in the grid.
+/// at any point in the grid. Lines can be added and removed after
+/// construction, the grid grows to fit every line added.
+///
+/// Counts are kept either in a dense matrix or, for inputs with
+/// few lines spread over a large area, in a sparse hash map.
 ///
 /// The print output from the Display trait for Grid struct is
 /// the visual Solution to part 1.
+use std::collections::HashMap;
 use std::fmt;
 
-use crate::common::{Coordinate, Count, PointCount};
+use crate::clip::{clip_line, clip_steps};
+use crate::common::{Bounds, Coordinate, Count, LineMode, PointCount, Vector};
 use crate::file::PointsData;
 use crate::line::LineIterator;
 
+/// The number of grid cells per covered point above which the
+/// sparse backend is chosen automatically.
+const SPARSE_RATIO: u64 = 8;
+
+/// How the counts of a Grid are stored.
+/// Dense allocates every cell of the bounding box,
+/// Sparse only stores the points covered by at least one line.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Backend {
+    Dense,
+    Sparse,
+}
+
+impl Backend {
+    /// Picks a backend from the area of the bounding box compared
+    /// to the number of points the lines will cover.
+    pub fn for_points(points: &PointsData, mode: LineMode) -> Self {
+        let area = (points.max_x.abs_diff(points.min_x).saturating_add(1))
+            .saturating_mul(points.max_y.abs_diff(points.min_y).saturating_add(1));
+        let covered = points
+            .iter()
+            .filter(|(start, end)| mode.accepts(start, end))
+            .map(|(start, end)| start.chebyshev_distance(end).saturating_add(1))
+            .fold(0u64, u64::saturating_add);
+        Self::for_density(area, covered)
+    }
+
+    /// Picks a backend for a grid limited to the window, counting only
+    /// the parts of the lines inside of it.
+    pub fn for_window(points: &PointsData, mode: LineMode, window: &Bounds) -> Self {
+        let area = window.width().saturating_mul(window.height());
+        let covered: u64 = points
+            .iter()
+            .filter(|(start, end)| mode.accepts(start, end))
+            .map(|&(start, end)| match clip_line(start, end, window) {
+                Ok(clipped) => clipped.map_or(0, |(start, end)| start.chebyshev_distance(&end) + 1),
+                Err(_) => clip_steps(start, end, window).map_or(0, |(from, to)| to - from + 1),
+            })
+            .fold(0u64, u64::saturating_add);
+        Self::for_density(area, covered)
+    }
+
+    fn for_density(area: u64, covered: u64) -> Self {
+        if area > covered.saturating_mul(SPARSE_RATIO) || area > usize::MAX as u64 {
+            Backend::Sparse
+        } else {
+            Backend::Dense
+        }
+    }
+}
+
+enum Cells {
+    Dense(Vec<Vec<usize>>),
+    Sparse(HashMap<Coordinate, usize>),
+}
+
+/// The grid covers the cells of its bounds, whose minimum corner is
+/// the top left origin of the grid.
+/// The lines added so far are kept so they can be removed again.
 pub struct Grid {
-    cells: Vec<Vec<usize>>,
+    cells: Cells,
+    bounds: Bounds,
+    lines: Vec<(Coordinate, Coordinate)>,
 }
 
 impl Grid {
-    pub fn new(points: PointsData) -> Self {
-        let mut grid = Self {
-            cells: vec![vec![0usize; points.max_x + 1]; points.max_y + 1],
+    /// Creates a 1x1 grid at the origin without any lines.
+    pub fn empty(backend: Backend) -> Self {
+        Self::with_backend(
+            PointsData::from_pairs(Vec::new()),
+            LineMode::AnySlope,
+            backend,
+        )
+    }
+
+    pub fn new(points: PointsData, mode: LineMode) -> Self {
+        let backend = Backend::for_points(&points, mode);
+        Self::with_backend(points, mode, backend)
+    }
+
+    /// Creates a grid covering the bounds without any lines.
+    fn blank(bounds: Bounds, backend: Backend) -> Self {
+        let cells = match backend {
+            Backend::Dense => Cells::Dense(vec![
+                vec![0usize; bounds.width() as usize];
+                bounds.height() as usize
+            ]),
+            Backend::Sparse => Cells::Sparse(HashMap::new()),
         };
-        points.iter().for_each(|coordinate| {
-            grid.add_line(coordinate.0, coordinate.1);
-        });
+        Self {
+            cells,
+            bounds,
+            lines: Vec::new(),
+        }
+    }
+
+    pub fn with_backend(points: PointsData, mode: LineMode, backend: Backend) -> Self {
+        let bounds = Bounds {
+            min: Coordinate {
+                x: points.min_x,
+                y: points.min_y,
+            },
+            max: Coordinate {
+                x: points.max_x,
+                y: points.max_y,
+            },
+        };
+        let mut grid = Self::blank(bounds, backend);
+        points
+            .iter()
+            .filter(|(start, end)| mode.accepts(start, end))
+            .for_each(|coordinate| {
+                grid.add_line(coordinate.0, coordinate.1);
+            });
+        grid
+    }
+
+    /// Creates a grid covering only the window. Lines are clipped to
+    /// the window before they are added, so the size of the grid does
+    /// not depend on the lines.
+    pub fn for_window(points: PointsData, mode: LineMode, window: Bounds) -> Self {
+        let backend = Backend::for_window(&points, mode, &window);
+        Self::with_window(points, mode, window, backend)
+    }
+
+    /// Like `for_window` with an explicit backend. Lines that are not
+    /// horizontal, vertical or 45 degrees are not clipped, instead only
+    /// their steps within the window along the major axis are walked
+    /// (see `clip_steps`), and they are not kept in `lines`.
+    pub fn with_window(
+        points: PointsData,
+        mode: LineMode,
+        window: Bounds,
+        backend: Backend,
+    ) -> Self {
+        let mut grid = Self::blank(window, backend);
+        for &(start, end) in points
+            .iter()
+            .filter(|(start, end)| mode.accepts(start, end))
+        {
+            match clip_line(start, end, &window) {
+                Ok(Some((start, end))) => grid.add_line(start, end),
+                Ok(None) => {}
+                Err(_) => {
+                    if let Some((from, to)) = clip_steps(start, end, &window) {
+                        LineIterator::from_step(start, end, from)
+                            .take((to - from + 1) as usize)
+                            .filter(|point| window.contains(point))
+                            .for_each(|point| grid.add_point(point));
+                    }
+                }
+            }
+        }
         grid
     }
 
+    pub fn backend(&self) -> Backend {
+        match self.cells {
+            Cells::Dense(_) => Backend::Dense,
+            Cells::Sparse(_) => Backend::Sparse,
+        }
+    }
+
+    /// The top left (minimum) corner of the grid.
+    pub fn origin(&self) -> Coordinate {
+        self.bounds.min
+    }
+
+    /// The number of columns, saturating at usize::MAX for a grid
+    /// spanning the whole range of i64.
+    pub fn width(&self) -> usize {
+        self.bounds.width() as usize
+    }
+
+    /// The number of rows, saturating like `width`.
+    pub fn height(&self) -> usize {
+        self.bounds.height() as usize
+    }
+
+    /// The coordinates covered by the grid.
+    pub fn bounds(&self) -> Bounds {
+        self.bounds
+    }
+
+    /// Returns the count of the cell at (column, row) from the origin.
+    pub fn cell(&self, column: usize, row: usize) -> usize {
+        self.count_at(
+            &(self.bounds.min
+                + Vector {
+                    dx: column as i64,
+                    dy: row as i64,
+                }),
+        )
+    }
+
+    /// Returns the highest count of any cell in the grid.
+    pub fn max_count(&self) -> usize {
+        match &self.cells {
+            Cells::Dense(cells) => cells.iter().flatten().copied().max(),
+            Cells::Sparse(cells) => cells.values().copied().max(),
+        }
+        .unwrap_or(0)
+    }
+
+    /// Returns the (column, row) offset of a point from the origin,
+    /// or None if the point lies outside the grid.
+    fn offset(&self, point: &Coordinate) -> Option<(usize, usize)> {
+        self.bounds.contains(point).then(|| {
+            (
+                point.x.abs_diff(self.bounds.min.x) as usize,
+                point.y.abs_diff(self.bounds.min.y) as usize,
+            )
+        })
+    }
+
+    /// Returns the lines added to the grid, in the order they were added.
+    pub fn lines(&self) -> &[(Coordinate, Coordinate)] {
+        &self.lines
+    }
+
+    /// Grows the grid so it contains both corners. A dense grid whose
+    /// grown area would be too large for the points it covers, counting
+    /// the new line, switches to the sparse backend instead.
+    fn grow_to_fit(&mut self, start: &Coordinate, end: &Coordinate) {
+        let bounds = self.bounds().union(&Bounds::new(*start, *end));
+        if bounds == self.bounds {
+            return;
+        }
+        if self.backend() == Backend::Dense {
+            let area = bounds.width().saturating_mul(bounds.height());
+            let covered = (self.covered_cells().count() as u64)
+                .saturating_add(start.chebyshev_distance(end).saturating_add(1));
+            if Backend::for_density(area, covered) == Backend::Sparse {
+                self.cells = Cells::Sparse(self.covered_cells().collect());
+            }
+        }
+        if let Cells::Dense(cells) = &mut self.cells {
+            let (width, height) = (bounds.width() as usize, bounds.height() as usize);
+            let shift = self.bounds.min - bounds.min;
+            let (column, row) = (shift.dx as usize, shift.dy as usize);
+            let mut grown = vec![vec![0usize; width]; height];
+            for (old_row, counts) in cells.iter().enumerate() {
+                grown[row + old_row][column..column + counts.len()].copy_from_slice(counts);
+            }
+            *cells = grown;
+        }
+        self.bounds = bounds;
+    }
+
     fn add_point(&mut self, point: Coordinate) {
-        self.cells[point.y][point.x] += 1;
+        let (column, row) = self.offset(&point).expect("point outside of grid");
+        match &mut self.cells {
+            Cells::Dense(cells) => cells[row][column] += 1,
+            Cells::Sparse(cells) => *cells.entry(point).or_insert(0) += 1,
+        }
     }
 
-    fn add_line(&mut self, start: Coordinate, end: Coordinate) {
+    fn remove_point(&mut self, point: Coordinate) {
+        let (column, row) = self.offset(&point).expect("point outside of grid");
+        match &mut self.cells {
+            Cells::Dense(cells) => cells[row][column] -= 1,
+            Cells::Sparse(cells) => {
+                let count = cells.get_mut(&point).expect("point not covered");
+                *count -= 1;
+                if *count == 0 {
+                    cells.remove(&point);
+                }
+            }
+        }
+    }
+
+    /// Adds a line of any slope, growing the grid when the line
+    /// lies (partly) outside of it.
+    ///
+    /// Panics if the line spans more than MAX_SPAN along an axis,
+    /// which parsing reports as a LineTooLong error.
+    pub fn add_line(&mut self, start: Coordinate, end: Coordinate) {
+        self.grow_to_fit(&start, &end);
         LineIterator::new(start, end)
             .collect::<Vec<Coordinate>>()
             .iter()
             .for_each(|point| {
                 self.add_point(*point);
             });
+        self.lines.push((start, end));
+    }
+
+    /// Removes a line added before, named by its ends in either order,
+    /// decrementing the counts it covered. Returns false if no such line
+    /// was added. The grid does not shrink.
+    pub fn remove_line(&mut self, start: Coordinate, end: Coordinate) -> bool {
+        let Some(index) = self
+            .lines
+            .iter()
+            .position(|&line| line == (start, end) || line == (end, start))
+        else {
+            return false;
+        };
+        // Walk the line as it was added, since Bresenham may pick other
+        // points when walking in the opposite direction.
+        let (start, end) = self.lines.remove(index);
+        LineIterator::new(start, end).for_each(|point| self.remove_point(point));
+        true
+    }
+
+    /// Removes every line, keeping the size of the grid.
+    pub fn clear(&mut self) {
+        match &mut self.cells {
+            Cells::Dense(cells) => cells.iter_mut().for_each(|row| row.fill(0)),
+            Cells::Sparse(cells) => cells.clear(),
+        }
+        self.lines.clear();
+    }
+
+    fn count_at(&self, point: &Coordinate) -> usize {
+        match &self.cells {
+            Cells::Dense(cells) => match self.offset(point) {
+                Some((column, row)) => cells[row][column],
+                None => 0,
+            },
+            Cells::Sparse(cells) => cells.get(point).copied().unwrap_or(0),
+        }
     }
 
     pub fn get_count(&self, point: &Coordinate) -> Option<PointCount> {
-        if point.y < self.cells.len() && point.x < self.cells[point.y].len() {
-            Some(PointCount {
-                point: *point,
-                count: Count(self.cells[point.y][point.x]),
+        self.offset(point).map(|_| PointCount {
+            point: *point,
+            count: Count(self.count_at(point)),
+        })
+    }
+
+    pub fn sum_double_counts(&self) -> i32 {
+        self.count_at_least(2) as i32
+    }
+
+    /// Iterates over the cells covered by at least one line,
+    /// in no particular order.
+    fn covered_cells(&self) -> Box<dyn Iterator<Item = (Coordinate, usize)> + '_> {
+        match &self.cells {
+            Cells::Dense(cells) => {
+                Box::new(cells.iter().enumerate().flat_map(move |(row, counts)| {
+                    counts
+                        .iter()
+                        .enumerate()
+                        .filter(|(_, &count)| count > 0)
+                        .map(move |(column, &count)| {
+                            let point = Coordinate {
+                                x: self.bounds.min.x + column as i64,
+                                y: self.bounds.min.y + row as i64,
+                            };
+                            (point, count)
+                        })
+                }))
+            }
+            Cells::Sparse(cells) => Box::new(cells.iter().map(|(&point, &count)| (point, count))),
+        }
+    }
+
+    /// Returns the number of cells of the grid, saturating at u128::MAX
+    /// for a grid spanning the whole range of i64 on both axes.
+    pub fn cell_total(&self) -> u128 {
+        let width = self.bounds.max.x.abs_diff(self.bounds.min.x) as u128 + 1;
+        let height = self.bounds.max.y.abs_diff(self.bounds.min.y) as u128 + 1;
+        width.saturating_mul(height)
+    }
+
+    /// Returns the number of cells not covered by any line,
+    /// saturating like `cell_total`.
+    pub fn empty_cells(&self) -> u128 {
+        self.cell_total() - self.covered_cells().count() as u128
+    }
+
+    /// Returns the number of points whose count is at least `threshold`.
+    /// With a threshold of 0 every cell is counted, saturating at
+    /// usize::MAX for huge sparse grids; see `cell_total`.
+    pub fn count_at_least(&self, threshold: usize) -> usize {
+        if threshold == 0 {
+            return usize::try_from(self.cell_total()).unwrap_or(usize::MAX);
+        }
+        self.covered_cells()
+            .filter(|&(_, count)| count >= threshold)
+            .count()
+    }
+
+    /// Returns the points whose count is at least `threshold`,
+    /// ordered row by row. Only covered points are returned,
+    /// so a threshold of 0 behaves like a threshold of 1.
+    pub fn points_at_least(&self, threshold: usize) -> Vec<PointCount> {
+        let mut points: Vec<PointCount> = self
+            .covered_cells()
+            .filter(|&(_, count)| count >= threshold)
+            .map(|(point, count)| PointCount {
+                point,
+                count: Count(count),
             })
-        } else {
-            None
+            .collect();
+        points.sort_by_key(|point_count| (point_count.point.y, point_count.point.x));
+        points
+    }
+
+    /// Returns how many cells have each count: `histogram[c]` is the
+    /// number of cells covered by exactly `c` lines. The number of empty
+    /// cells saturates at usize::MAX for huge sparse grids; see `empty_cells`.
+    pub fn histogram(&self) -> Vec<usize> {
+        let mut histogram = vec![0; self.max_count() + 1];
+        for (_, count) in self.covered_cells() {
+            histogram[count] += 1;
         }
+        histogram[0] = usize::try_from(self.empty_cells()).unwrap_or(usize::MAX);
+        histogram
     }
 
-    pub fn sum_double_counts(&self) -> i32 {
-        self.cells
-            .iter()
-            .flatten()
-            .filter(|&&count| count >= 2)
-            .count() as i32
+    /// Returns the `n` points with the highest counts, highest first.
+    /// Points with the same count are ordered row by row.
+    pub fn top_points(&self, n: usize) -> Vec<PointCount> {
+        let mut points: Vec<(Coordinate, usize)> = self.covered_cells().collect();
+        points.sort_by_key(|&(point, count)| (std::cmp::Reverse(count), point.y, point.x));
+        points
+            .into_iter()
+            .take(n)
+            .map(|(point, count)| PointCount {
+                point,
+                count: Count(count),
+            })
+            .collect()
+    }
+
+    /// Returns the sum of the counts in the rectangle spanned by two
+    /// corners, both included. Parts outside the grid count as 0.
+    pub fn region_sum(&self, corner: &Coordinate, opposite: &Coordinate) -> usize {
+        let region = Bounds::new(*corner, *opposite);
+        match &self.cells {
+            Cells::Dense(cells) => {
+                let Some(region) = region.intersection(&self.bounds()) else {
+                    return 0;
+                };
+                let (min, max) = (region.min - self.bounds.min, region.max - self.bounds.min);
+                cells[min.dy as usize..=max.dy as usize]
+                    .iter()
+                    .map(|row| row[min.dx as usize..=max.dx as usize].iter().sum::<usize>())
+                    .sum()
+            }
+            Cells::Sparse(cells) => cells
+                .iter()
+                .filter(|(point, _)| region.contains(point))
+                .map(|(_, &count)| count)
+                .sum(),
+        }
     }
 }
 
 impl fmt::Display for Grid {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         // writeln!(f, "Grid:")?;
-        for row in &self.cells {
-            for &cell in row {
+        for row in 0..self.height() {
+            for column in 0..self.width() {
+                let cell = self.cell(column, row);
                 if cell == 0 {
                     write!(f, ".")?;
                 } else {
@@ -79,7 +489,7 @@ impl fmt::Display for Grid {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::file::PointsData;
+    use crate::file::{read_file_to_points, PointsData};
 
     #[test]
     fn test_grid_new() {
@@ -88,12 +498,15 @@ mod tests {
                 (Coordinate { x: 0, y: 0 }, Coordinate { x: 0, y: 1 }),
                 (Coordinate { x: 0, y: 1 }, Coordinate { x: 1, y: 1 }),
             ],
+            line_numbers: vec![1, 2],
+            min_x: 0,
+            min_y: 0,
             max_x: 1,
             max_y: 1,
         };
-        let grid = Grid::new(points_data);
-        assert_eq!(grid.cells.len(), 2);
-        assert_eq!(grid.cells[0].len(), 2);
+        let grid = Grid::new(points_data, LineMode::AxisAligned);
+        assert_eq!(grid.height(), 2);
+        assert_eq!(grid.width(), 2);
     }
 
     #[test]
@@ -103,10 +516,13 @@ mod tests {
                 (Coordinate { x: 0, y: 0 }, Coordinate { x: 0, y: 1 }),
                 (Coordinate { x: 2, y: 2 }, Coordinate { x: 3, y: 3 }),
             ],
+            line_numbers: vec![1, 2],
+            min_x: 0,
+            min_y: 0,
             max_x: 1,
             max_y: 1,
         };
-        let grid = Grid::new(points_data);
+        let grid = Grid::new(points_data, LineMode::AxisAligned);
         let count_origin = grid.get_count(&Coordinate { x: 0, y: 0 });
         assert_eq!(
             count_origin,
@@ -116,4 +532,358 @@ mod tests {
             })
         );
     }
+
+    #[test]
+    fn test_grid_sample_both_parts() {
+        let points = read_file_to_points("data/sample1.txt", LineMode::AxisAligned).unwrap();
+        assert_eq!(
+            Grid::new(points, LineMode::AxisAligned).sum_double_counts(),
+            5
+        );
+
+        let points = read_file_to_points("data/sample1.txt", LineMode::WithDiagonals).unwrap();
+        assert_eq!(
+            Grid::new(points, LineMode::WithDiagonals).sum_double_counts(),
+            12
+        );
+    }
+
+    #[test]
+    fn test_grid_backend_auto() {
+        let dense = PointsData {
+            point_pairs: vec![(Coordinate { x: 0, y: 0 }, Coordinate { x: 3, y: 0 })],
+            line_numbers: vec![1],
+            min_x: 0,
+            min_y: 0,
+            max_x: 3,
+            max_y: 0,
+        };
+        assert_eq!(
+            Grid::new(dense, LineMode::AxisAligned).backend(),
+            Backend::Dense
+        );
+
+        let sparse = PointsData {
+            point_pairs: vec![
+                (Coordinate { x: 0, y: 0 }, Coordinate { x: 1, y: 0 }),
+                (
+                    Coordinate {
+                        x: 100_000,
+                        y: 100_000,
+                    },
+                    Coordinate {
+                        x: 100_000,
+                        y: 99_999,
+                    },
+                ),
+            ],
+            line_numbers: vec![1, 2],
+            min_x: 0,
+            min_y: 0,
+            max_x: 100_000,
+            max_y: 100_000,
+        };
+        let grid = Grid::new(sparse, LineMode::AxisAligned);
+        assert_eq!(grid.backend(), Backend::Sparse);
+        assert_eq!(
+            grid.get_count(&Coordinate {
+                x: 100_000,
+                y: 99_999
+            }),
+            Some(PointCount {
+                point: Coordinate {
+                    x: 100_000,
+                    y: 99_999
+                },
+                count: Count(1)
+            })
+        );
+        assert_eq!(grid.get_count(&Coordinate { x: 100_001, y: 0 }), None);
+    }
+
+    #[test]
+    fn test_grid_far_apart_lines() {
+        let points = PointsData::from_pairs(vec![
+            (
+                Coordinate { x: i64::MIN, y: 0 },
+                Coordinate {
+                    x: i64::MIN + 1,
+                    y: 0,
+                },
+            ),
+            (
+                Coordinate {
+                    x: i64::MAX - 1,
+                    y: 0,
+                },
+                Coordinate { x: i64::MAX, y: 0 },
+            ),
+            (
+                Coordinate { x: i64::MAX, y: 1 },
+                Coordinate { x: i64::MAX, y: 0 },
+            ),
+        ]);
+        let grid = Grid::new(points, LineMode::AxisAligned);
+        assert_eq!(grid.backend(), Backend::Sparse);
+        assert_eq!(grid.width(), usize::MAX);
+        assert_eq!(grid.bounds().max, Coordinate { x: i64::MAX, y: 1 });
+        assert_eq!(
+            grid.get_count(&Coordinate { x: i64::MAX, y: 0 })
+                .map(|point_count| point_count.count),
+            Some(Count(2))
+        );
+        assert_eq!(grid.sum_double_counts(), 1);
+        assert_eq!(grid.count_at_least(0), usize::MAX);
+        assert_eq!(grid.histogram(), vec![usize::MAX, 4, 1]);
+
+        let grid = Grid::new(
+            PointsData::from_pairs(vec![
+                (
+                    Coordinate {
+                        x: -5_000_000_000,
+                        y: -5_000_000_000,
+                    },
+                    Coordinate {
+                        x: -5_000_000_000,
+                        y: -4_999_999_999,
+                    },
+                ),
+                (
+                    Coordinate {
+                        x: 5_000_000_000,
+                        y: 5_000_000_000,
+                    },
+                    Coordinate {
+                        x: 5_000_000_000,
+                        y: 5_000_000_000,
+                    },
+                ),
+            ]),
+            LineMode::AxisAligned,
+        );
+        assert_eq!(grid.cell_total(), 10_000_000_001 * 10_000_000_001);
+        assert_eq!(grid.empty_cells(), 10_000_000_001 * 10_000_000_001 - 3);
+        assert_eq!(grid.histogram(), vec![usize::MAX, 3]);
+    }
+
+    #[test]
+    fn test_grid_backends_agree() {
+        for mode in [LineMode::AxisAligned, LineMode::WithDiagonals] {
+            let dense = Grid::with_backend(
+                read_file_to_points("data/sample1.txt", mode).unwrap(),
+                mode,
+                Backend::Dense,
+            );
+            let sparse = Grid::with_backend(
+                read_file_to_points("data/sample1.txt", mode).unwrap(),
+                mode,
+                Backend::Sparse,
+            );
+            assert_eq!(dense.sum_double_counts(), sparse.sum_double_counts());
+            assert_eq!(dense.to_string(), sparse.to_string());
+        }
+    }
+
+    #[test]
+    fn test_grid_centred_on_origin() {
+        let points_data = PointsData {
+            point_pairs: vec![
+                (Coordinate { x: -2, y: 0 }, Coordinate { x: 2, y: 0 }),
+                (Coordinate { x: 0, y: -2 }, Coordinate { x: 0, y: 2 }),
+                (Coordinate { x: -2, y: -2 }, Coordinate { x: 2, y: 2 }),
+            ],
+            line_numbers: vec![1, 2, 3],
+            min_x: -2,
+            min_y: -2,
+            max_x: 2,
+            max_y: 2,
+        };
+        for backend in [Backend::Dense, Backend::Sparse] {
+            let grid = Grid::with_backend(
+                PointsData {
+                    point_pairs: points_data.point_pairs.clone(),
+                    line_numbers: points_data.line_numbers.clone(),
+                    ..points_data
+                },
+                LineMode::WithDiagonals,
+                backend,
+            );
+            assert_eq!(grid.sum_double_counts(), 1);
+            assert_eq!(
+                grid.get_count(&Coordinate { x: 0, y: 0 }).unwrap().count,
+                Count(3)
+            );
+            assert_eq!(grid.get_count(&Coordinate { x: -3, y: 0 }), None);
+            assert_eq!(grid.to_string(), "1.1..\n.11..\n11311\n..11.\n..1.1\n");
+            assert_eq!(
+                grid.points_at_least(2),
+                vec![PointCount {
+                    point: Coordinate { x: 0, y: 0 },
+                    count: Count(3)
+                }]
+            );
+            assert_eq!(grid.points_at_least(1).len(), 13);
+        }
+    }
+
+    #[test]
+    fn test_grid_queries() {
+        for backend in [Backend::Dense, Backend::Sparse] {
+            let points = read_file_to_points("data/sample1.txt", LineMode::WithDiagonals).unwrap();
+            let grid = Grid::with_backend(points, LineMode::WithDiagonals, backend);
+
+            assert_eq!(grid.count_at_least(2), 12);
+            assert_eq!(grid.count_at_least(3), 2);
+            assert_eq!(grid.count_at_least(0), 100);
+
+            let histogram = grid.histogram();
+            assert_eq!(histogram, vec![61, 27, 10, 2]);
+            assert_eq!(histogram.iter().sum::<usize>(), 100);
+
+            assert_eq!(
+                grid.top_points(3),
+                vec![
+                    PointCount {
+                        point: Coordinate { x: 4, y: 4 },
+                        count: Count(3)
+                    },
+                    PointCount {
+                        point: Coordinate { x: 6, y: 4 },
+                        count: Count(3)
+                    },
+                    PointCount {
+                        point: Coordinate { x: 7, y: 1 },
+                        count: Count(2)
+                    },
+                ]
+            );
+
+            // The row "222111...." at y = 9.
+            assert_eq!(
+                grid.region_sum(&Coordinate { x: 9, y: 9 }, &Coordinate { x: 0, y: 9 }),
+                9
+            );
+            assert_eq!(
+                grid.region_sum(&Coordinate { x: -5, y: -5 }, &Coordinate { x: 20, y: 20 }),
+                grid.histogram()
+                    .iter()
+                    .enumerate()
+                    .map(|(count, cells)| count * cells)
+                    .sum::<usize>()
+            );
+            assert_eq!(
+                grid.region_sum(&Coordinate { x: 20, y: 20 }, &Coordinate { x: 30, y: 30 }),
+                0
+            );
+        }
+    }
+
+    #[test]
+    fn test_grid_incremental() {
+        for backend in [Backend::Dense, Backend::Sparse] {
+            let mut grid = Grid::empty(backend);
+            assert_eq!((grid.width(), grid.height()), (1, 1));
+
+            grid.add_line(Coordinate { x: 0, y: 0 }, Coordinate { x: 2, y: 0 });
+            grid.add_line(Coordinate { x: 1, y: -2 }, Coordinate { x: 1, y: 1 });
+            assert_eq!(grid.origin(), Coordinate { x: 0, y: -2 });
+            assert_eq!((grid.width(), grid.height()), (3, 4));
+            assert_eq!(grid.to_string(), ".1.\n.1.\n121\n.1.\n");
+            assert_eq!(grid.sum_double_counts(), 1);
+
+            assert!(grid.remove_line(Coordinate { x: 2, y: 0 }, Coordinate { x: 0, y: 0 }));
+            assert!(!grid.remove_line(Coordinate { x: 0, y: 0 }, Coordinate { x: 2, y: 0 }));
+            assert_eq!(grid.to_string(), ".1.\n.1.\n.1.\n.1.\n");
+            assert_eq!(grid.sum_double_counts(), 0);
+            assert_eq!(grid.lines().len(), 1);
+
+            grid.add_line(Coordinate { x: 0, y: -2 }, Coordinate { x: 2, y: -1 });
+            assert!(grid.remove_line(Coordinate { x: 2, y: -1 }, Coordinate { x: 0, y: -2 }));
+            assert_eq!(grid.to_string(), ".1.\n.1.\n.1.\n.1.\n");
+
+            grid.clear();
+            assert_eq!(grid.max_count(), 0);
+            assert!(grid.lines().is_empty());
+            assert_eq!((grid.width(), grid.height()), (3, 4));
+        }
+    }
+
+    #[test]
+    fn test_grid_dense_grows_sparse() {
+        let far = Coordinate {
+            x: 1_000_000_000_000,
+            y: 1_000_000_000_000,
+        };
+        let sample = read_file_to_points("data/sample1.txt", LineMode::AxisAligned).unwrap();
+        let mut grid = Grid::new(sample, LineMode::AxisAligned);
+        assert_eq!(grid.backend(), Backend::Dense);
+        grid.add_line(far, far + Vector { dx: 2, dy: 0 });
+        assert_eq!(grid.backend(), Backend::Sparse);
+        assert_eq!(grid.sum_double_counts(), 5);
+        assert_eq!(grid.get_count(&far).unwrap().count.0, 1);
+
+        let mut grid = Grid::empty(Backend::Dense);
+        grid.add_line(far, far);
+        assert_eq!(grid.backend(), Backend::Sparse);
+        assert_eq!(grid.bounds(), Bounds::new(Coordinate { x: 0, y: 0 }, far));
+        assert_eq!(grid.points_at_least(1).len(), 1);
+    }
+
+    #[test]
+    fn test_grid_for_window_long_line() {
+        // Walking this line from end to end would take about 2^61 steps.
+        let window = Bounds::new(Coordinate { x: 0, y: 0 }, Coordinate { x: 9, y: 9 });
+        let (start, end) = (
+            Coordinate {
+                x: -(1 << 60),
+                y: -(1 << 59),
+            },
+            Coordinate {
+                x: 1 << 60,
+                y: 1 << 59,
+            },
+        );
+        let points = PointsData::from_pairs(vec![(start, end)]);
+        let grid = Grid::for_window(points, LineMode::AnySlope, window);
+        assert_eq!(grid.bounds(), window);
+        let covered: Vec<Coordinate> = grid.points_at_least(1).iter().map(|p| p.point).collect();
+        assert_eq!(covered.len(), 10);
+        assert!(covered.contains(&Coordinate { x: 0, y: 0 }));
+        assert!(covered.contains(&Coordinate { x: 8, y: 4 }));
+    }
+
+    #[test]
+    fn test_grid_for_window() {
+        let window = Bounds::new(Coordinate { x: 2, y: 1 }, Coordinate { x: 7, y: 4 });
+        for mode in [LineMode::AxisAligned, LineMode::WithDiagonals] {
+            let full = Grid::new(read_file_to_points("data/sample1.txt", mode).unwrap(), mode);
+            for backend in [Backend::Dense, Backend::Sparse] {
+                let points = read_file_to_points("data/sample1.txt", mode).unwrap();
+                let grid = Grid::with_window(points, mode, window, backend);
+                assert_eq!(grid.bounds(), window);
+                assert_eq!(
+                    grid.sum_double_counts() as usize,
+                    full.points_at_least(2)
+                        .iter()
+                        .filter(|point_count| window.contains(&point_count.point))
+                        .count()
+                );
+            }
+        }
+
+        let points = PointsData::from_pairs(vec![
+            (Coordinate { x: 0, y: 0 }, Coordinate { x: 9, y: 4 }),
+            (Coordinate { x: 0, y: 2 }, Coordinate { x: 9, y: 2 }),
+            (
+                Coordinate {
+                    x: -1_000_000,
+                    y: 3,
+                },
+                Coordinate { x: 1_000_000, y: 3 },
+            ),
+        ]);
+        let grid = Grid::for_window(points, LineMode::AnySlope, window);
+        assert_eq!(grid.backend(), Backend::Dense);
+        assert_eq!(grid.to_string(), "11....\n112211\n111122\n......\n");
+    }
 }
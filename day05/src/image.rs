@@ -0,0 +1,151 @@
+/// This file exports a Grid as a heatmap image in the Netpbm formats.
+/// Each cell is drawn as a `scale` x `scale` block of pixels,
+/// coloured by its count relative to the highest count in the grid.
+///
+/// PGM output is greyscale, from black for empty cells to white for
+/// the most overlapped cells. PPM output uses a colour ramp going
+/// from dark blue through green and yellow to red.
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+
+use crate::grid::Grid;
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ImageFormat {
+    Pgm,
+    Ppm,
+}
+
+/// The colours of the PPM ramp, from the lowest to the highest count.
+const RAMP: [[u8; 3]; 5] = [
+    [0, 0, 96],
+    [0, 128, 255],
+    [0, 200, 0],
+    [255, 220, 0],
+    [255, 0, 0],
+];
+
+/// Returns the grey level for a count, 0 for an empty cell.
+pub fn grey_level(count: usize, max_count: usize) -> u8 {
+    if count == 0 || max_count == 0 {
+        0
+    } else {
+        // Non-empty cells start at a visible grey.
+        (64 + (count.min(max_count) * 191) / max_count) as u8
+    }
+}
+
+/// Returns the RGB colour for a count, black for an empty cell.
+pub fn heat_colour(count: usize, max_count: usize) -> [u8; 3] {
+    if count == 0 || max_count == 0 {
+        return [0, 0, 0];
+    }
+    if max_count == 1 {
+        return RAMP[0];
+    }
+    // Position of the count on the ramp, in 1/256ths of a ramp step.
+    let steps = RAMP.len() - 1;
+    let position = (count.min(max_count) - 1) * steps * 256 / (max_count - 1);
+    let (index, fraction) = (position / 256, position % 256);
+    if index >= steps {
+        return RAMP[steps];
+    }
+    let (from, to) = (RAMP[index], RAMP[index + 1]);
+    let mut colour = [0u8; 3];
+    for channel in 0..3 {
+        let (from, to) = (from[channel] as usize, to[channel] as usize);
+        colour[channel] = ((from * (256 - fraction) + to * fraction) / 256) as u8;
+    }
+    colour
+}
+
+/// Writes the grid as a binary PGM (P5) or PPM (P6) image.
+pub fn write_image<W: Write>(
+    grid: &Grid,
+    writer: &mut W,
+    format: ImageFormat,
+    scale: usize,
+) -> io::Result<()> {
+    let scale = scale.max(1);
+    let max_count = grid.max_count();
+    let magic = match format {
+        ImageFormat::Pgm => "P5",
+        ImageFormat::Ppm => "P6",
+    };
+    writeln!(
+        writer,
+        "{}\n{} {}\n255",
+        magic,
+        grid.width() * scale,
+        grid.height() * scale
+    )?;
+
+    let mut line = Vec::new();
+    for row in 0..grid.height() {
+        line.clear();
+        for column in 0..grid.width() {
+            let count = grid.cell(column, row);
+            for _ in 0..scale {
+                match format {
+                    ImageFormat::Pgm => line.push(grey_level(count, max_count)),
+                    ImageFormat::Ppm => line.extend_from_slice(&heat_colour(count, max_count)),
+                }
+            }
+        }
+        for _ in 0..scale {
+            writer.write_all(&line)?;
+        }
+    }
+    writer.flush()
+}
+
+/// Saves the grid as an image file at the given path.
+pub fn save_image(grid: &Grid, path: &str, format: ImageFormat, scale: usize) -> io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    write_image(grid, &mut writer, format, scale)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::{Coordinate, LineMode};
+    use crate::file::PointsData;
+
+    fn small_grid() -> Grid {
+        let points = PointsData::from_pairs(vec![
+            (Coordinate { x: 0, y: 0 }, Coordinate { x: 1, y: 0 }),
+            (Coordinate { x: 1, y: 0 }, Coordinate { x: 1, y: 1 }),
+        ]);
+        Grid::new(points, LineMode::AxisAligned)
+    }
+
+    #[test]
+    fn test_heat_colour_ramp() {
+        assert_eq!(heat_colour(0, 5), [0, 0, 0]);
+        assert_eq!(heat_colour(1, 5), RAMP[0]);
+        assert_eq!(heat_colour(5, 5), RAMP[4]);
+        assert_eq!(heat_colour(9, 5), RAMP[4]);
+        assert_eq!(heat_colour(1, 1), RAMP[0]);
+    }
+
+    #[test]
+    fn test_write_image_pgm() {
+        let mut output = Vec::new();
+        write_image(&small_grid(), &mut output, ImageFormat::Pgm, 1).unwrap();
+        let header = b"P5\n2 2\n255\n";
+        assert_eq!(&output[..header.len()], header);
+        assert_eq!(&output[header.len()..], &[159, 255, 0, 159]);
+    }
+
+    #[test]
+    fn test_write_image_ppm_scaled() {
+        let mut output = Vec::new();
+        write_image(&small_grid(), &mut output, ImageFormat::Ppm, 3).unwrap();
+        let header = b"P6\n6 6\n255\n";
+        assert_eq!(&output[..header.len()], header);
+        assert_eq!(output.len(), header.len() + 6 * 6 * 3);
+        // The top right 3x3 block is the cell with the highest count.
+        let pixel = header.len() + 5 * 3;
+        assert_eq!(&output[pixel..pixel + 3], &RAMP[4]);
+    }
+}
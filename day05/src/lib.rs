@@ -1,4 +1,13 @@
+pub mod clip;
 pub mod common;
+pub mod error;
+pub mod export;
 pub mod file;
 pub mod grid;
+pub mod image;
 pub mod line;
+pub mod overlap;
+pub mod provenance;
+pub mod render;
+pub mod svg;
+pub mod volume;
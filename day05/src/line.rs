@@ -5,41 +5,99 @@
 ///
 /// The LineIterator struct is an iterator that generates all coordinates
 /// from the start to the end of a line. Order doesn't matter.
-use crate::common::Coordinate;
+/// Horizontal, vertical and 45 degree lines are walked one step at a
+/// time on both axes, any other slope is rasterized with Bresenham's
+/// integer algorithm.
+use crate::common::{Coordinate, Vector};
 
-pub struct Step {
-    x: i32,
-    y: i32,
+/// The state of Bresenham's algorithm: the absolute distances
+/// (dy negated) and the accumulated error.
+struct Bresenham {
+    dx: i64,
+    dy: i64,
+    error: i64,
 }
 
 pub struct LineIterator {
     current: Coordinate,
     end: Coordinate,
-    step: Step,
+    step: Vector,
     done: bool,
+    bresenham: Option<Bresenham>,
 }
 
 impl LineIterator {
     pub fn new(start: Coordinate, end: Coordinate) -> Self {
-        let step = Step {
-            x: if start.x <= end.x { 1 } else { -1 },
-            y: if start.y <= end.y { 1 } else { -1 },
+        let step = (end - start).signum();
+        let (dx, dy) = (start.x.abs_diff(end.x), start.y.abs_diff(end.y));
+        let bresenham = if dx == 0 || dy == 0 || dx == dy {
+            None
+        } else {
+            let (dx, dy) = (dx as i64, -(dy as i64));
+            Some(Bresenham {
+                dx,
+                dy,
+                error: dx + dy,
+            })
         };
         Self {
             current: start,
             end,
             step,
             done: false,
+            bresenham,
         }
     }
 
+    /// Creates an iterator that starts `steps` steps along the line, as
+    /// if `next` had been called that many times. The major axis advances
+    /// on every step, and Bresenham's minor steps and error follow from
+    /// the number of steps, so this takes constant time.
+    ///
+    /// Panics if the line has fewer than `steps` steps.
+    pub fn from_step(start: Coordinate, end: Coordinate, steps: u64) -> Self {
+        let mut iterator = Self::new(start, end);
+        let (dx, dy) = (
+            start.x.abs_diff(end.x) as i128,
+            start.y.abs_diff(end.y) as i128,
+        );
+        let k = steps as i128;
+        assert!(k <= dx.max(dy), "skipping past the end of the line");
+        let (x_steps, y_steps) = match &mut iterator.bresenham {
+            None => (k.min(dx), k.min(dy)),
+            Some(bresenham) => {
+                // The number of minor steps after k major steps is k times
+                // the slope, rounded half up.
+                let (x_steps, y_steps) = if dx > dy {
+                    (k, (2 * k * dy + dx) / (2 * dx))
+                } else {
+                    ((2 * k * dx + dy) / (2 * dy), k)
+                };
+                bresenham.error = (dx - dy - x_steps * dy + y_steps * dx) as i64;
+                (x_steps, y_steps)
+            }
+        };
+        iterator.current = Coordinate {
+            x: (start.x as i128 + x_steps * iterator.step.dx as i128) as i64,
+            y: (start.y as i128 + y_steps * iterator.step.dy as i128) as i64,
+        };
+        iterator
+    }
+
     fn update_current(&mut self) {
-        if self.current.x != self.end.x {
-            self.current.x = (self.current.x as i32 + self.step.x) as usize;
-        }
-        if self.current.y != self.end.y {
-            self.current.y = (self.current.y as i32 + self.step.y) as usize;
+        if let Some(bresenham) = &mut self.bresenham {
+            let double_error = 2 * bresenham.error;
+            if double_error >= bresenham.dy {
+                bresenham.error += bresenham.dy;
+                self.current.x += self.step.dx;
+            }
+            if double_error <= bresenham.dx {
+                bresenham.error += bresenham.dx;
+                self.current.y += self.step.dy;
+            }
+            return;
         }
+        self.current += self.step;
     }
 }
 
@@ -106,4 +164,55 @@ mod tests {
         ];
         assert_eq!(line.line_coordinates, expected_coordinates);
     }
+
+    #[test]
+    fn test_to_line_coords_negative() {
+        let line = Line::new(Coordinate { x: -1, y: 1 }, Coordinate { x: 1, y: -1 });
+        let expected_coordinates = vec![
+            Coordinate { x: -1, y: 1 },
+            Coordinate { x: 0, y: 0 },
+            Coordinate { x: 1, y: -1 },
+        ];
+        assert_eq!(line.line_coordinates, expected_coordinates);
+    }
+
+    #[test]
+    fn test_to_line_coords_any_slope() {
+        let line = Line::new(Coordinate { x: 0, y: 0 }, Coordinate { x: 4, y: 2 });
+        let expected_coordinates = vec![
+            Coordinate { x: 0, y: 0 },
+            Coordinate { x: 1, y: 1 },
+            Coordinate { x: 2, y: 1 },
+            Coordinate { x: 3, y: 2 },
+            Coordinate { x: 4, y: 2 },
+        ];
+        assert_eq!(line.line_coordinates, expected_coordinates);
+
+        let line = Line::new(Coordinate { x: 1, y: 5 }, Coordinate { x: 0, y: 0 });
+        assert_eq!(line.line_coordinates.len(), 6);
+        assert_eq!(
+            line.line_coordinates.first(),
+            Some(&Coordinate { x: 1, y: 5 })
+        );
+        assert_eq!(
+            line.line_coordinates.last(),
+            Some(&Coordinate { x: 0, y: 0 })
+        );
+    }
+
+    #[test]
+    fn test_line_iterator_from_step() {
+        let ends = [(0, 0), (7, 3), (-5, 9), (4, 4), (0, -6), (-11, -2), (3, -8)];
+        for &(x1, y1) in &ends {
+            for &(x2, y2) in &ends {
+                let (start, end) = (Coordinate { x: x1, y: y1 }, Coordinate { x: x2, y: y2 });
+                let points: Vec<Coordinate> = LineIterator::new(start, end).collect();
+                for steps in 0..points.len() {
+                    let skipped: Vec<Coordinate> =
+                        LineIterator::from_step(start, end, steps as u64).collect();
+                    assert_eq!(skipped, points[steps..], "{:?} -> {:?}", start, end);
+                }
+            }
+        }
+    }
 }
@@ -1,10 +1,32 @@
-use day05::file::read_file_to_points;
+use std::env;
+use std::process;
+
+use day05::common::LineMode;
+use day05::file::{open_input, read_points, STDIN_PATH};
 use day05::grid::Grid;
 
+const DEFAULT_PATH: &str = "data/data1.txt";
+
+/// Usage: day05 [PATH]
+/// Reads the vent lines from PATH, or from standard input when PATH is `-`,
+/// and prints the answers to both parts.
 fn main() {
-    let path = String::from("data/data1.txt");
-    let points = read_file_to_points(&path).unwrap();
-    let grid = Grid::new(points);
-    // println!("{}", grid)
-    print!("{}", grid.sum_double_counts())
+    let path = env::args()
+        .nth(1)
+        .unwrap_or_else(|| DEFAULT_PATH.to_string());
+    let name = if path == STDIN_PATH { "<stdin>" } else { &path };
+    let reader = open_input(&path).unwrap_or_else(|err| {
+        eprintln!("{}: {}", name, err);
+        process::exit(1);
+    });
+    // Keep every line, each part only counts the lines of its mode.
+    let points = read_points(reader, name, LineMode::AnySlope).unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        process::exit(1);
+    });
+    for (part, mode) in [(1, LineMode::AxisAligned), (2, LineMode::WithDiagonals)] {
+        let grid = Grid::new(points.clone(), mode);
+        // println!("{}", grid)
+        println!("Part {}: {}", part, grid.sum_double_counts())
+    }
 }
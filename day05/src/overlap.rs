@@ -0,0 +1,369 @@
+/// This file counts the points where at least two lines overlap
+/// directly from the segments, without painting them into a Grid.
+///
+/// Every supported segment lies on a line `a*x + b*y = key` of one of
+/// four directions: horizontal, vertical, diagonal or anti-diagonal.
+/// Segments of the same direction can only overlap when they share a key,
+/// which is found by sweeping their intervals. Segments of different
+/// directions meet in at most one point, found by solving the two
+/// line equations. Memory and time only depend on the number of
+/// segments and intersections, not on the size of the coordinates.
+/// Keys and positions are computed in i128, so segments anywhere in the
+/// range of i64 are handled without overflow.
+///
+/// Segments of any other slope are rasterized and each of their
+/// points is counted as a one point horizontal segment.
+use std::collections::{BTreeMap, HashSet};
+
+use crate::common::{Coordinate, LineMode};
+use crate::file::PointsData;
+use crate::line::LineIterator;
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum Direction {
+    Horizontal,
+    Vertical,
+    Diagonal,
+    AntiDiagonal,
+}
+
+const DIRECTIONS: [Direction; 4] = [
+    Direction::Horizontal,
+    Direction::Vertical,
+    Direction::Diagonal,
+    Direction::AntiDiagonal,
+];
+
+impl Direction {
+    fn of(start: &Coordinate, end: &Coordinate) -> Self {
+        if start.y == end.y {
+            Direction::Horizontal
+        } else if start.x == end.x {
+            Direction::Vertical
+        } else if (end.x > start.x) == (end.y > start.y) {
+            Direction::Diagonal
+        } else {
+            Direction::AntiDiagonal
+        }
+    }
+
+    /// The (a, b) coefficients of the line equation `a*x + b*y = key`.
+    fn coefficients(&self) -> (i128, i128) {
+        match self {
+            Direction::Horizontal => (0, 1),
+            Direction::Vertical => (1, 0),
+            Direction::Diagonal => (1, -1),
+            Direction::AntiDiagonal => (1, 1),
+        }
+    }
+
+    /// Returns the key of the line through the point and the
+    /// position of the point along that line.
+    fn key_and_position(&self, point: &Coordinate) -> (i128, i128) {
+        let (a, b) = self.coefficients();
+        let (x, y) = (point.x as i128, point.y as i128);
+        let position = match self {
+            Direction::Vertical => y,
+            _ => x,
+        };
+        (a * x + b * y, position)
+    }
+}
+
+/// The intervals covered by the segments of one direction, per key.
+/// `union` holds the points covered at least once,
+/// `multiple` the points covered at least twice.
+#[derive(Default)]
+struct Coverage {
+    union: BTreeMap<i128, Vec<(i128, i128)>>,
+    multiple: BTreeMap<i128, Vec<(i128, i128)>>,
+}
+
+impl Coverage {
+    fn new(intervals: BTreeMap<i128, Vec<(i128, i128)>>) -> Self {
+        let mut coverage = Coverage::default();
+        for (key, intervals) in intervals {
+            let mut events: Vec<(i128, i64)> = intervals
+                .iter()
+                .flat_map(|&(from, to)| [(from, 1), (to + 1, -1)])
+                .collect();
+            events.sort_unstable();
+
+            let mut union = Vec::new();
+            let mut multiple = Vec::new();
+            let mut depth = 0;
+            let mut union_start = 0;
+            let mut multiple_start = 0;
+            for (position, change) in events {
+                let previous = depth;
+                depth += change;
+                if previous < 1 && depth >= 1 {
+                    union_start = position;
+                } else if previous >= 1 && depth < 1 {
+                    push_interval(&mut union, union_start, position - 1);
+                }
+                if previous < 2 && depth >= 2 {
+                    multiple_start = position;
+                } else if previous >= 2 && depth < 2 {
+                    push_interval(&mut multiple, multiple_start, position - 1);
+                }
+            }
+            coverage.union.insert(key, union);
+            if !multiple.is_empty() {
+                coverage.multiple.insert(key, multiple);
+            }
+        }
+        coverage
+    }
+
+    /// The number of points covered at least twice, saturating at u128::MAX.
+    fn multiple_points(&self) -> u128 {
+        self.multiple
+            .values()
+            .flatten()
+            .map(|(from, to)| (to - from + 1) as u128)
+            .fold(0, u128::saturating_add)
+    }
+
+    fn is_multiple(&self, key: i128, position: i128) -> bool {
+        self.multiple.get(&key).is_some_and(|intervals| {
+            let index = intervals.partition_point(|&(_, to)| to < position);
+            index < intervals.len() && intervals[index].0 <= position
+        })
+    }
+
+    fn intervals(&self) -> impl Iterator<Item = (i128, (i128, i128))> + '_ {
+        self.union
+            .iter()
+            .flat_map(|(&key, intervals)| intervals.iter().map(move |&interval| (key, interval)))
+    }
+}
+
+/// Appends an interval, joining it with the previous one when adjacent.
+fn push_interval(intervals: &mut Vec<(i128, i128)>, from: i128, to: i128) {
+    match intervals.last_mut() {
+        Some(last) if last.1 + 1 >= from => last.1 = last.1.max(to),
+        _ => intervals.push((from, to)),
+    }
+}
+
+/// Returns the integer point where the lines `key1` of `first` and
+/// `key2` of `second` cross, if there is one within the range of i64.
+fn crossing(first: Direction, key1: i128, second: Direction, key2: i128) -> Option<Coordinate> {
+    let (a1, b1) = first.coefficients();
+    let (a2, b2) = second.coefficients();
+    let det = a1 * b2 - a2 * b1;
+    let x = key1 * b2 - key2 * b1;
+    let y = a1 * key2 - a2 * key1;
+    if det == 0 || x % det != 0 || y % det != 0 {
+        return None;
+    }
+    Some(Coordinate {
+        x: i64::try_from(x / det).ok()?,
+        y: i64::try_from(y / det).ok()?,
+    })
+}
+
+/// Counts the points where at least two of the lines considered
+/// in the given mode overlap. Gives the same answer as
+/// `Grid::sum_double_counts` for the same points and mode.
+/// Saturates at usize::MAX, which only a few lines spanning most of
+/// the range of i64 can reach.
+pub fn count_overlaps(points: &PointsData, mode: LineMode) -> usize {
+    let mut intervals: Vec<BTreeMap<i128, Vec<(i128, i128)>>> = vec![BTreeMap::new(); 4];
+    for (start, end) in points
+        .iter()
+        .filter(|(start, end)| mode.accepts(start, end))
+    {
+        if !LineMode::WithDiagonals.accepts(start, end) {
+            for point in LineIterator::new(*start, *end) {
+                intervals[Direction::Horizontal as usize]
+                    .entry(point.y as i128)
+                    .or_default()
+                    .push((point.x as i128, point.x as i128));
+            }
+            continue;
+        }
+        let direction = Direction::of(start, end);
+        let (key, from) = direction.key_and_position(start);
+        let (_, to) = direction.key_and_position(end);
+        intervals[direction as usize]
+            .entry(key)
+            .or_default()
+            .push((from.min(to), from.max(to)));
+    }
+    let coverages: Vec<Coverage> = intervals.into_iter().map(Coverage::new).collect();
+
+    // Points covered by segments of at least two directions.
+    let mut crossings = HashSet::new();
+    for (i, &first) in DIRECTIONS.iter().enumerate() {
+        for &second in &DIRECTIONS[i + 1..] {
+            for (key1, (from1, to1)) in coverages[first as usize].intervals() {
+                for (key2, (from2, to2)) in coverages[second as usize].intervals() {
+                    let Some(point) = crossing(first, key1, second, key2) else {
+                        continue;
+                    };
+                    let (_, position1) = first.key_and_position(&point);
+                    let (_, position2) = second.key_and_position(&point);
+                    if (from1..=to1).contains(&position1) && (from2..=to2).contains(&position2) {
+                        crossings.insert(point);
+                    }
+                }
+            }
+        }
+    }
+
+    // Points covered twice within one direction, not already crossings.
+    let multiple = coverages
+        .iter()
+        .map(Coverage::multiple_points)
+        .fold(0, u128::saturating_add);
+    let counted_twice: usize = crossings
+        .iter()
+        .map(|point| {
+            DIRECTIONS
+                .iter()
+                .filter(|&&direction| {
+                    let (key, position) = direction.key_and_position(point);
+                    coverages[direction as usize].is_multiple(key, position)
+                })
+                .count()
+        })
+        .sum();
+
+    let total = (crossings.len() as u128).saturating_add(multiple) - counted_twice as u128;
+    usize::try_from(total).unwrap_or(usize::MAX)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::file::read_file_to_points;
+    use crate::grid::Grid;
+
+    #[test]
+    fn test_count_overlaps_matches_grid() {
+        for path in ["data/sample1.txt", "data/data1.txt"] {
+            for mode in [LineMode::AxisAligned, LineMode::WithDiagonals] {
+                let points = read_file_to_points(path, mode).unwrap();
+                let expected = Grid::new(points.clone(), mode).sum_double_counts() as usize;
+                assert_eq!(
+                    count_overlaps(&points, mode),
+                    expected,
+                    "{} {:?}",
+                    path,
+                    mode
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_count_overlaps_collinear_and_crossing() {
+        let points = PointsData::from_pairs(vec![
+            (Coordinate { x: 0, y: 0 }, Coordinate { x: 4, y: 0 }),
+            (Coordinate { x: 2, y: 0 }, Coordinate { x: 6, y: 0 }),
+            (Coordinate { x: 3, y: -3 }, Coordinate { x: 3, y: 3 }),
+            (Coordinate { x: 0, y: 3 }, Coordinate { x: 3, y: 0 }),
+        ]);
+        // (2,0) (3,0) (4,0) overlap horizontally, (3,0) is also crossed
+        // by the vertical and the anti-diagonal line.
+        assert_eq!(count_overlaps(&points, LineMode::WithDiagonals), 3);
+        assert_eq!(count_overlaps(&points, LineMode::AxisAligned), 3);
+    }
+
+    #[test]
+    fn test_count_overlaps_any_slope_matches_grid() {
+        let points = PointsData::from_pairs(vec![
+            (Coordinate { x: 0, y: 0 }, Coordinate { x: 9, y: 4 }),
+            (Coordinate { x: 0, y: 4 }, Coordinate { x: 9, y: 0 }),
+            (Coordinate { x: 4, y: 0 }, Coordinate { x: 5, y: 9 }),
+            (Coordinate { x: 0, y: 2 }, Coordinate { x: 9, y: 2 }),
+            (Coordinate { x: 0, y: 0 }, Coordinate { x: 7, y: 7 }),
+        ]);
+        let expected = Grid::new(points.clone(), LineMode::AnySlope).sum_double_counts() as usize;
+        assert!(expected > 0);
+        assert_eq!(count_overlaps(&points, LineMode::AnySlope), expected);
+    }
+
+    #[test]
+    fn test_count_overlaps_extreme_coordinates() {
+        let points = PointsData::from_pairs(vec![
+            (Coordinate { x: 0, y: 0 }, Coordinate { x: i64::MAX, y: 0 }),
+            (
+                Coordinate {
+                    x: i64::MAX - 2,
+                    y: 0,
+                },
+                Coordinate { x: i64::MAX, y: 0 },
+            ),
+            (
+                Coordinate {
+                    x: i64::MAX,
+                    y: i64::MIN,
+                },
+                Coordinate { x: i64::MAX, y: 0 },
+            ),
+            (
+                Coordinate {
+                    x: i64::MIN,
+                    y: i64::MAX,
+                },
+                Coordinate {
+                    x: i64::MIN + 1,
+                    y: i64::MAX - 1,
+                },
+            ),
+            (
+                Coordinate {
+                    x: i64::MIN,
+                    y: i64::MAX - 1,
+                },
+                Coordinate {
+                    x: i64::MIN + 1,
+                    y: i64::MAX,
+                },
+            ),
+        ]);
+        // The last three points of the first line, and no point of the
+        // two tiny crossing diagonals since they meet between points.
+        assert_eq!(count_overlaps(&points, LineMode::WithDiagonals), 3);
+    }
+
+    #[test]
+    fn test_count_overlaps_huge_coordinates() {
+        let points = PointsData::from_pairs(vec![
+            (
+                Coordinate { x: 0, y: 0 },
+                Coordinate {
+                    x: 4_000_000,
+                    y: 4_000_000,
+                },
+            ),
+            (
+                Coordinate { x: 4_000_000, y: 0 },
+                Coordinate { x: 0, y: 4_000_000 },
+            ),
+            (
+                Coordinate { x: 0, y: 2_000_000 },
+                Coordinate {
+                    x: 4_000_000,
+                    y: 2_000_000,
+                },
+            ),
+            (
+                Coordinate {
+                    x: 1_000_000,
+                    y: 1_000_000,
+                },
+                Coordinate {
+                    x: 3_000_000,
+                    y: 3_000_000,
+                },
+            ),
+        ]);
+        // The diagonals overlap on 2_000_001 points, one of which
+        // is also where every other line crosses.
+        assert_eq!(count_overlaps(&points, LineMode::WithDiagonals), 2_000_001);
+    }
+}
@@ -0,0 +1,134 @@
+/// This file tracks which input lines cover each point, to explain
+/// the counts reported by a Grid. Lines are identified by their index
+/// in `PointsData::point_pairs`, including the lines that are skipped
+/// by the LineMode, and `line_number` maps an index to the input line
+/// it was read from, for display.
+use std::collections::{BTreeSet, HashMap};
+
+use crate::common::{Coordinate, LineMode};
+use crate::file::PointsData;
+use crate::line::LineIterator;
+
+pub struct Provenance {
+    segments: HashMap<Coordinate, Vec<usize>>,
+    line_numbers: Vec<usize>,
+}
+
+impl Provenance {
+    pub fn new(points: &PointsData, mode: LineMode) -> Self {
+        let mut segments: HashMap<Coordinate, Vec<usize>> = HashMap::new();
+        points
+            .iter()
+            .enumerate()
+            .filter(|(_, (start, end))| mode.accepts(start, end))
+            .for_each(|(index, (start, end))| {
+                for point in LineIterator::new(*start, *end) {
+                    segments.entry(point).or_default().push(index);
+                }
+            });
+        let line_numbers = (0..points.point_pairs.len())
+            .map(|index| points.line_number(index))
+            .collect();
+        Self {
+            segments,
+            line_numbers,
+        }
+    }
+
+    /// Returns the 1-based input line number of the line at `index`,
+    /// or None if there is no such line.
+    pub fn line_number(&self, index: usize) -> Option<usize> {
+        self.line_numbers.get(index).copied()
+    }
+
+    /// Returns the indices of the lines covering the point, in input order.
+    pub fn segments_at(&self, point: &Coordinate) -> &[usize] {
+        self.segments.get(point).map_or(&[], Vec::as_slice)
+    }
+
+    /// Returns every point covered by at least two lines together with
+    /// the indices of those lines, ordered row by row.
+    pub fn overlaps(&self) -> Vec<(Coordinate, &[usize])> {
+        let mut overlaps: Vec<(Coordinate, &[usize])> = self
+            .segments
+            .iter()
+            .filter(|(_, segments)| segments.len() >= 2)
+            .map(|(&point, segments)| (point, segments.as_slice()))
+            .collect();
+        overlaps.sort_by_key(|(point, _)| (point.y, point.x));
+        overlaps
+    }
+
+    /// Returns every pair of lines that share at least one point,
+    /// each pair ordered by index and the pairs sorted.
+    pub fn intersecting_pairs(&self) -> Vec<(usize, usize)> {
+        let mut pairs = BTreeSet::new();
+        for segments in self.segments.values() {
+            for (i, &first) in segments.iter().enumerate() {
+                for &second in &segments[i + 1..] {
+                    pairs.insert((first, second));
+                }
+            }
+        }
+        pairs.into_iter().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::file::{read_file_to_points, read_points};
+    use crate::grid::Grid;
+
+    #[test]
+    fn test_provenance_sample() {
+        let points = read_file_to_points("data/sample1.txt", LineMode::WithDiagonals).unwrap();
+        let provenance = Provenance::new(&points, LineMode::WithDiagonals);
+
+        // 0,9 -> 5,9 and 0,9 -> 2,9 form the bottom row.
+        assert_eq!(provenance.segments_at(&Coordinate { x: 1, y: 9 }), &[0, 6]);
+        // 8,0 -> 0,8, 9,4 -> 3,4 and 0,0 -> 8,8 meet at 4,4.
+        assert_eq!(
+            provenance.segments_at(&Coordinate { x: 4, y: 4 }),
+            &[1, 2, 8]
+        );
+        assert!(provenance
+            .segments_at(&Coordinate { x: 100, y: 100 })
+            .is_empty());
+
+        let grid = Grid::new(points, LineMode::WithDiagonals);
+        let overlaps = provenance.overlaps();
+        assert_eq!(overlaps.len(), grid.count_at_least(2));
+        for (point, segments) in overlaps {
+            assert_eq!(grid.get_count(&point).unwrap().count.0, segments.len());
+        }
+
+        let pairs = provenance.intersecting_pairs();
+        assert!(pairs.contains(&(0, 6)));
+        assert!(pairs.iter().all(|(first, second)| first < second));
+    }
+
+    #[test]
+    fn test_provenance_keeps_input_indices() {
+        let points = read_file_to_points("data/sample1.txt", LineMode::WithDiagonals).unwrap();
+        let provenance = Provenance::new(&points, LineMode::AxisAligned);
+        // 8,0 -> 0,8 is line 1 and skipped in this mode.
+        assert_eq!(provenance.segments_at(&Coordinate { x: 4, y: 4 }), &[2]);
+        assert_eq!(provenance.segments_at(&Coordinate { x: 7, y: 4 }), &[2, 4]);
+        let index = provenance.segments_at(&Coordinate { x: 7, y: 4 })[1];
+        assert_eq!(
+            points.point_pairs[index],
+            (Coordinate { x: 7, y: 0 }, Coordinate { x: 7, y: 4 })
+        );
+        assert_eq!(provenance.line_number(index), Some(5));
+
+        // Lines dropped while parsing, and blank lines, keep their numbers.
+        let input = "0,0 -> 2,2\n\n0,0 -> 0,0\n0,0 -> 1,0\n";
+        let points = read_points(input.as_bytes(), "test", LineMode::AxisAligned).unwrap();
+        let provenance = Provenance::new(&points, LineMode::AxisAligned);
+        assert_eq!(provenance.segments_at(&Coordinate { x: 0, y: 0 }), &[0, 1]);
+        assert_eq!(provenance.line_number(0), Some(3));
+        assert_eq!(provenance.line_number(1), Some(4));
+        assert_eq!(provenance.line_number(2), None);
+    }
+}
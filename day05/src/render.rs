@@ -0,0 +1,144 @@
+/// This file renders a rectangular part of a Grid for the terminal.
+/// Unlike the Display trait of Grid, the rendering is limited to a
+/// Viewport, labels the x axis on top (digits stacked vertically) and
+/// the y axis on the left, and can colour the cells by count with
+/// ANSI escape codes.
+///
+/// Every cell takes one character: `.` for an empty cell, the count
+/// for counts up to 9, `+` above that and a space outside of the grid.
+use std::fmt;
+
+use crate::common::{Bounds, Coordinate};
+use crate::grid::Grid;
+
+const RESET: &str = "\x1b[0m";
+
+/// A rectangle of the grid, both corners included.
+/// `Grid::bounds` gives the viewport covering the whole grid.
+pub type Viewport = Bounds;
+
+/// Returns the ANSI colour escape for a count.
+fn colour_of(count: usize) -> &'static str {
+    match count {
+        0 => "\x1b[2m",
+        1 => "\x1b[34m",
+        2 => "\x1b[32m",
+        3 => "\x1b[33m",
+        _ => "\x1b[1;31m",
+    }
+}
+
+fn symbol_of(count: Option<usize>) -> char {
+    match count {
+        None => ' ',
+        Some(0) => '.',
+        Some(count) if count <= 9 => char::from_digit(count as u32, 10).unwrap(),
+        Some(_) => '+',
+    }
+}
+
+pub struct Render<'a> {
+    grid: &'a Grid,
+    viewport: Viewport,
+    colour: bool,
+}
+
+impl<'a> Render<'a> {
+    pub fn new(grid: &'a Grid, viewport: Viewport) -> Self {
+        Self {
+            grid,
+            viewport,
+            colour: false,
+        }
+    }
+
+    /// Enables or disables ANSI colours.
+    pub fn with_colour(mut self, colour: bool) -> Self {
+        self.colour = colour;
+        self
+    }
+}
+
+impl fmt::Display for Render<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Viewport { min, max } = self.viewport;
+        let label_width = [min.y, max.y]
+            .iter()
+            .map(|y| y.to_string().len())
+            .max()
+            .unwrap_or(1);
+
+        // The x labels, right aligned and read from top to bottom.
+        let x_labels: Vec<String> = (min.x..=max.x).map(|x| x.to_string()).collect();
+        let label_height = x_labels.iter().map(String::len).max().unwrap_or(0);
+        for line in 0..label_height {
+            write!(f, "{:width$} ", "", width = label_width)?;
+            for label in &x_labels {
+                let padding = label_height - label.len();
+                let symbol = match line.checked_sub(padding) {
+                    Some(index) => label.as_bytes()[index] as char,
+                    None => ' ',
+                };
+                write!(f, "{}", symbol)?;
+            }
+            writeln!(f)?;
+        }
+
+        for y in min.y..=max.y {
+            write!(f, "{:>width$} ", y, width = label_width)?;
+            for x in min.x..=max.x {
+                let count = self
+                    .grid
+                    .get_count(&Coordinate { x, y })
+                    .map(|point_count| point_count.count.0);
+                let symbol = symbol_of(count);
+                match count {
+                    Some(count) if self.colour => {
+                        write!(f, "{}{}{}", colour_of(count), symbol, RESET)?
+                    }
+                    _ => write!(f, "{}", symbol)?,
+                }
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::LineMode;
+    use crate::file::read_file_to_points;
+    use crate::grid::Backend;
+
+    #[test]
+    fn test_render_viewport() {
+        let points = read_file_to_points("data/sample1.txt", LineMode::WithDiagonals).unwrap();
+        let grid = Grid::new(points, LineMode::WithDiagonals);
+
+        let viewport = Viewport::new(Coordinate { x: 11, y: 4 }, Coordinate { x: 6, y: 3 });
+        let expected = "      11\n  678901\n3 .2..  \n4 3211  \n";
+        assert_eq!(Render::new(&grid, viewport).to_string(), expected);
+    }
+
+    #[test]
+    fn test_render_whole_grid_matches_display() {
+        let points = read_file_to_points("data/sample1.txt", LineMode::AxisAligned).unwrap();
+        let grid = Grid::new(points, LineMode::AxisAligned);
+        let rendered = Render::new(&grid, grid.bounds()).to_string();
+        let cells: Vec<&str> = rendered.lines().skip(1).map(|line| &line[2..]).collect();
+        assert_eq!(cells.join("\n") + "\n", grid.to_string());
+    }
+
+    #[test]
+    fn test_render_colour() {
+        let mut grid = Grid::empty(Backend::Dense);
+        grid.add_line(Coordinate { x: 0, y: 0 }, Coordinate { x: 1, y: 0 });
+        grid.add_line(Coordinate { x: 1, y: 0 }, Coordinate { x: 1, y: 0 });
+        let rendered = Render::new(&grid, grid.bounds())
+            .with_colour(true)
+            .to_string();
+        assert_eq!(rendered, "  01\n0 \x1b[34m1\x1b[0m\x1b[32m2\x1b[0m\n");
+    }
+}
@@ -0,0 +1,119 @@
+/// This file renders the vent lines of PointsData as an SVG drawing.
+/// Every segment is drawn as one stroke and every overlap point as a
+/// circle whose radius grows with its count. The viewBox covers the
+/// bounding box of the points with a margin of one unit, so the drawing
+/// can be zoomed without losing detail.
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+
+use crate::common::PointCount;
+use crate::file::PointsData;
+
+const MARGIN: i128 = 1;
+const STROKE_WIDTH: f64 = 0.3;
+const RADIUS_PER_COUNT: f64 = 0.2;
+
+/// Writes the segments and the overlap points as an SVG document.
+pub fn write_svg<W: Write>(
+    writer: &mut W,
+    points: &PointsData,
+    overlaps: &[PointCount],
+) -> io::Result<()> {
+    // The margin and the size can go past i64 for extreme coordinates.
+    let (min_x, min_y) = (points.min_x as i128, points.min_y as i128);
+    let (max_x, max_y) = (points.max_x as i128, points.max_y as i128);
+    writeln!(
+        writer,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="{} {} {} {}">"#,
+        min_x - MARGIN,
+        min_y - MARGIN,
+        max_x - min_x + 2 * MARGIN,
+        max_y - min_y + 2 * MARGIN
+    )?;
+
+    writeln!(
+        writer,
+        r#"  <g stroke="steelblue" stroke-width="{}" stroke-linecap="round">"#,
+        STROKE_WIDTH
+    )?;
+    for (start, end) in points.iter() {
+        writeln!(
+            writer,
+            r#"    <line x1="{}" y1="{}" x2="{}" y2="{}"/>"#,
+            start.x, start.y, end.x, end.y
+        )?;
+    }
+    writeln!(writer, "  </g>")?;
+
+    writeln!(writer, r#"  <g fill="crimson" fill-opacity="0.7">"#)?;
+    for overlap in overlaps {
+        writeln!(
+            writer,
+            r#"    <circle cx="{}" cy="{}" r="{:.2}"><title>{}</title></circle>"#,
+            overlap.point.x,
+            overlap.point.y,
+            RADIUS_PER_COUNT * overlap.count.0 as f64,
+            overlap
+        )?;
+    }
+    writeln!(writer, "  </g>")?;
+    writeln!(writer, "</svg>")?;
+    writer.flush()
+}
+
+/// Saves the segments and the overlap points as an SVG file.
+pub fn save_svg(path: &str, points: &PointsData, overlaps: &[PointCount]) -> io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    write_svg(&mut writer, points, overlaps)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::{Coordinate, LineMode};
+    use crate::grid::Grid;
+
+    #[test]
+    fn test_write_svg() {
+        let points = PointsData::from_pairs(vec![
+            (Coordinate { x: 0, y: 0 }, Coordinate { x: 2, y: 0 }),
+            (Coordinate { x: 1, y: 0 }, Coordinate { x: 1, y: 3 }),
+        ]);
+        let overlaps = Grid::new(points.clone(), LineMode::AxisAligned).points_at_least(2);
+
+        let mut output = Vec::new();
+        write_svg(&mut output, &points, &overlaps).unwrap();
+        let svg = String::from_utf8(output).unwrap();
+
+        assert!(svg.starts_with(r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="-1 -1 4 5">"#));
+        assert_eq!(svg.matches("<line ").count(), 2);
+        assert!(svg.contains(r#"<line x1="1" y1="0" x2="1" y2="3"/>"#));
+        assert_eq!(svg.matches("<circle ").count(), 1);
+        assert!(svg.contains(r#"<circle cx="1" cy="0" r="0.40">"#));
+        assert!(svg.trim_end().ends_with("</svg>"));
+    }
+
+    #[test]
+    fn test_write_svg_extreme_coordinates() {
+        let points = PointsData::from_pairs(vec![
+            (
+                Coordinate { x: i64::MIN, y: 0 },
+                Coordinate { x: i64::MIN, y: 2 },
+            ),
+            (
+                Coordinate { x: i64::MAX, y: 0 },
+                Coordinate { x: i64::MAX, y: 2 },
+            ),
+        ]);
+
+        let mut output = Vec::new();
+        write_svg(&mut output, &points, &[]).unwrap();
+        let svg = String::from_utf8(output).unwrap();
+
+        assert!(svg.starts_with(&format!(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="{} -1 {} 4">"#,
+            i64::MIN as i128 - 1,
+            u64::MAX as i128 + 2
+        )));
+    }
+}
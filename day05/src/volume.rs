@@ -0,0 +1,408 @@
+/// This file extends the vent lines to three dimensions.
+/// A Coordinate3 is parsed from `x,y,z` and a line from `x,y,z -> x,y,z`.
+///
+/// Line3Iterator walks axis-aligned lines, where only one axis changes,
+/// and diagonal lines, where every changing axis changes by the same
+/// amount. Lines of any other slope are reported as UnsupportedSlope,
+/// also in LineMode::AnySlope.
+///
+/// A Volume keeps the count of every covered point in a hash map, so
+/// its memory does not depend on the size of the bounding box, and
+/// offers the same overlap queries as Grid.
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::fmt;
+use std::io::BufRead;
+use std::str::FromStr;
+
+use crate::common::{parse_number, Count, LineMode, MAX_SPAN};
+use crate::error::{ColumnError, ParseError, ParseErrorKind, ReadError, SlopeError};
+use crate::file::{parse_lines_with, parse_segment};
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Coordinate3 {
+    pub x: i64,
+    pub y: i64,
+    pub z: i64,
+}
+
+impl FromStr for Coordinate3 {
+    type Err = ColumnError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let missing_comma = |column| ColumnError {
+            column,
+            kind: ParseErrorKind::MissingComma,
+        };
+        let (x, rest) = s
+            .split_once(',')
+            .ok_or(missing_comma(s.len() - s.trim_start().len() + 1))?;
+        let (y, z) = rest
+            .split_once(',')
+            .ok_or(missing_comma(s.len() - rest.len() + 1))?;
+        Ok(Coordinate3 {
+            x: parse_number(x)?,
+            y: parse_number(y).map_err(|err| err.shifted(s.len() - rest.len()))?,
+            z: parse_number(z).map_err(|err| err.shifted(s.len() - z.len()))?,
+        })
+    }
+}
+
+impl fmt::Display for Coordinate3 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "({},{},{})", self.x, self.y, self.z)
+    }
+}
+
+/// A 3D vent line from its start to its end coordinate.
+pub type Segment3 = (Coordinate3, Coordinate3);
+
+#[derive(PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PointCount3 {
+    pub point: Coordinate3,
+    pub count: Count,
+}
+
+impl fmt::Display for PointCount3 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Point {}: Count={}", self.point, self.count.0)
+    }
+}
+
+/// Returns the number of changing axes and the number of steps of
+/// the line, or None if the line is neither axis-aligned nor diagonal.
+fn shape(start: &Coordinate3, end: &Coordinate3) -> Option<(usize, u64)> {
+    let deltas = [
+        start.x.abs_diff(end.x),
+        start.y.abs_diff(end.y),
+        start.z.abs_diff(end.z),
+    ];
+    let length = deltas.iter().copied().max().unwrap_or(0);
+    if deltas.iter().all(|&delta| delta == 0 || delta == length) {
+        Some((deltas.iter().filter(|&&delta| delta > 0).count(), length))
+    } else {
+        None
+    }
+}
+
+/// Returns true if the line spans at most MAX_SPAN along every axis,
+/// like `common::within_span` for 2D lines.
+fn within_span3(start: &Coordinate3, end: &Coordinate3) -> bool {
+    start.x.abs_diff(end.x) <= MAX_SPAN
+        && start.y.abs_diff(end.y) <= MAX_SPAN
+        && start.z.abs_diff(end.z) <= MAX_SPAN
+}
+
+/// Returns Some(true) if the mode considers the line, Some(false) if the
+/// mode skips it, and None if the slope is not supported at all.
+fn accepts(mode: LineMode, start: &Coordinate3, end: &Coordinate3) -> Option<bool> {
+    let (axes, _) = shape(start, end)?;
+    Some(mode != LineMode::AxisAligned || axes <= 1)
+}
+
+/// Parses a line like `0,9,1 -> 5,9,1`.
+/// Returns None for a valid line that is not considered in the given mode.
+fn parse_line3(input: &str, mode: LineMode) -> Result<Option<Segment3>, ColumnError> {
+    let (start, end) = parse_segment::<Coordinate3>(input)?;
+    let column = input.len() - input.trim_start().len() + 1;
+
+    if !within_span3(&start, &end) {
+        return Err(ColumnError {
+            column,
+            kind: ParseErrorKind::LineTooLong,
+        });
+    }
+    match accepts(mode, &start, &end) {
+        Some(true) => Ok(Some((start, end))),
+        Some(false) => Ok(None),
+        None if mode == LineMode::AxisAligned => Ok(None),
+        None => Err(ColumnError {
+            column,
+            kind: SlopeError.into(),
+        }),
+    }
+}
+
+/// Reads 3D vent lines from any reader, stopping at the first line
+/// that cannot be parsed. The name is used as the file in errors.
+pub fn read_points3<R: BufRead>(
+    reader: R,
+    name: &str,
+    mode: LineMode,
+) -> Result<Vec<Segment3>, ReadError> {
+    let (pairs, _) = parse_lines_with(reader, name, true, |line| parse_line3(line, mode))?;
+    Ok(pairs.into_iter().map(|(_, pair)| pair).collect())
+}
+
+/// Reads 3D vent lines from any reader, skipping lines that cannot be
+/// parsed and returning their errors alongside the parsed lines.
+pub fn read_points3_lenient<R: BufRead>(
+    reader: R,
+    name: &str,
+    mode: LineMode,
+) -> Result<(Vec<Segment3>, Vec<ParseError>), ReadError> {
+    let (pairs, errors) = parse_lines_with(reader, name, false, |line| parse_line3(line, mode))?;
+    Ok((pairs.into_iter().map(|(_, pair)| pair).collect(), errors))
+}
+
+/// Generates all coordinates from the start to the end of an
+/// axis-aligned or diagonal 3D line, both included.
+pub struct Line3Iterator {
+    current: Coordinate3,
+    step: Coordinate3,
+    remaining: Option<u64>,
+}
+
+impl Line3Iterator {
+    /// Returns a SlopeError if the line is neither axis-aligned nor diagonal.
+    pub fn new(start: Coordinate3, end: Coordinate3) -> Result<Self, SlopeError> {
+        let (_, length) = shape(&start, &end).ok_or(SlopeError)?;
+        Ok(Self {
+            current: start,
+            step: Coordinate3 {
+                x: end.x.cmp(&start.x) as i64,
+                y: end.y.cmp(&start.y) as i64,
+                z: end.z.cmp(&start.z) as i64,
+            },
+            remaining: Some(length),
+        })
+    }
+}
+
+impl Iterator for Line3Iterator {
+    type Item = Coordinate3;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let remaining = self.remaining?;
+        let result = self.current;
+        if remaining == 0 {
+            self.remaining = None;
+        } else {
+            self.remaining = Some(remaining - 1);
+            self.current.x += self.step.x;
+            self.current.y += self.step.y;
+            self.current.z += self.step.z;
+        }
+        Some(result)
+    }
+}
+
+/// A sparse count of how many lines cover each point in 3D.
+#[derive(Default)]
+pub struct Volume {
+    cells: HashMap<Coordinate3, usize>,
+}
+
+impl Volume {
+    /// Creates a volume with the lines considered in the given mode.
+    /// Lines of an unsupported slope are skipped.
+    pub fn new(pairs: &[Segment3], mode: LineMode) -> Self {
+        let mut volume = Volume::default();
+        pairs
+            .iter()
+            .filter(|(start, end)| accepts(mode, start, end) == Some(true))
+            .for_each(|&(start, end)| {
+                // Accepted lines always have a supported slope.
+                let _ = volume.add_line(start, end);
+            });
+        volume
+    }
+
+    /// Adds an axis-aligned or diagonal line. Returns a SlopeError,
+    /// leaving the volume unchanged, if the line has any other slope.
+    pub fn add_line(&mut self, start: Coordinate3, end: Coordinate3) -> Result<(), SlopeError> {
+        for point in Line3Iterator::new(start, end)? {
+            *self.cells.entry(point).or_insert(0) += 1;
+        }
+        Ok(())
+    }
+
+    pub fn get_count(&self, point: &Coordinate3) -> PointCount3 {
+        PointCount3 {
+            point: *point,
+            count: Count(self.cells.get(point).copied().unwrap_or(0)),
+        }
+    }
+
+    /// Returns the number of covered points whose count is at least `threshold`.
+    pub fn count_at_least(&self, threshold: usize) -> usize {
+        self.cells
+            .values()
+            .filter(|&&count| count > 0 && count >= threshold)
+            .count()
+    }
+
+    pub fn sum_double_counts(&self) -> i32 {
+        self.count_at_least(2) as i32
+    }
+
+    pub fn max_count(&self) -> usize {
+        self.cells.values().copied().max().unwrap_or(0)
+    }
+
+    /// Returns how many covered points have each count: `histogram[c]`
+    /// is the number of points covered by exactly `c` lines. The volume
+    /// is unbounded, so `histogram[0]` is always 0.
+    pub fn histogram(&self) -> Vec<usize> {
+        let mut histogram = vec![0; self.max_count() + 1];
+        for &count in self.cells.values() {
+            histogram[count] += 1;
+        }
+        histogram
+    }
+
+    /// Returns the `n` points with the highest counts, highest first.
+    /// Points with the same count are ordered by z, y and then x.
+    pub fn top_points(&self, n: usize) -> Vec<PointCount3> {
+        let mut points: Vec<(&Coordinate3, &usize)> = self.cells.iter().collect();
+        points.sort_by_key(|&(point, &count)| (Reverse(count), point.z, point.y, point.x));
+        points
+            .into_iter()
+            .take(n)
+            .map(|(&point, &count)| PointCount3 {
+                point,
+                count: Count(count),
+            })
+            .collect()
+    }
+
+    /// Returns the sum of the counts in the box spanned by two
+    /// corners, both included.
+    pub fn region_sum(&self, corner: &Coordinate3, opposite: &Coordinate3) -> usize {
+        let inside = |value: i64, a: i64, b: i64| (a.min(b)..=a.max(b)).contains(&value);
+        self.cells
+            .iter()
+            .filter(|(point, _)| {
+                inside(point.x, corner.x, opposite.x)
+                    && inside(point.y, corner.y, opposite.y)
+                    && inside(point.z, corner.z, opposite.z)
+            })
+            .map(|(_, &count)| count)
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn c(x: i64, y: i64, z: i64) -> Coordinate3 {
+        Coordinate3 { x, y, z }
+    }
+
+    #[test]
+    fn test_coordinate3_from_str() {
+        assert_eq!("1, -2,3".parse::<Coordinate3>(), Ok(c(1, -2, 3)));
+
+        let err = "1,2".parse::<Coordinate3>().unwrap_err();
+        assert_eq!(err.column, 3);
+        assert_eq!(err.kind, ParseErrorKind::MissingComma);
+
+        let err = "1,2,z".parse::<Coordinate3>().unwrap_err();
+        assert_eq!(err.column, 5);
+        assert!(matches!(err.kind, ParseErrorKind::BadInteger(_)));
+    }
+
+    #[test]
+    fn test_line3_iterator() {
+        let points: Vec<_> = Line3Iterator::new(c(0, 0, 2), c(2, 2, 0))
+            .unwrap()
+            .collect();
+        assert_eq!(points, vec![c(0, 0, 2), c(1, 1, 1), c(2, 2, 0)]);
+
+        let points: Vec<_> = Line3Iterator::new(c(1, 1, 1), c(1, 1, 1))
+            .unwrap()
+            .collect();
+        assert_eq!(points, vec![c(1, 1, 1)]);
+
+        assert_eq!(
+            Line3Iterator::new(c(0, 5, 0), c(0, 0, 0)).unwrap().count(),
+            6
+        );
+
+        assert!(matches!(
+            Line3Iterator::new(c(0, 0, 0), c(1, 2, 3)),
+            Err(SlopeError)
+        ));
+    }
+
+    #[test]
+    fn test_volume_add_line_unsupported_slope() {
+        let mut volume = Volume::default();
+        assert_eq!(volume.add_line(c(0, 0, 0), c(2, 0, 0)), Ok(()));
+        assert_eq!(volume.add_line(c(0, 0, 0), c(1, 2, 3)), Err(SlopeError));
+        assert_eq!(volume.count_at_least(1), 3);
+    }
+
+    #[test]
+    fn test_read_points3_modes() {
+        let input = "0,0,0 -> 2,0,0\n0,0,0 -> 2,2,0\n2,0,0 -> 2,0,3\n";
+        let axis = read_points3(input.as_bytes(), "buffer", LineMode::AxisAligned).unwrap();
+        assert_eq!(axis.len(), 2);
+        let all = read_points3(input.as_bytes(), "buffer", LineMode::WithDiagonals).unwrap();
+        assert_eq!(all.len(), 3);
+
+        let err =
+            read_points3("0,0,0 -> 1,2,3".as_bytes(), "buffer", LineMode::AnySlope).unwrap_err();
+        match err {
+            ReadError::Parse(err) => assert_eq!(err.kind, ParseErrorKind::UnsupportedSlope),
+            ReadError::Io(err) => panic!("unexpected I/O error: {}", err),
+        }
+
+        // Like 2D lines, 3D lines may span at most MAX_SPAN.
+        let err = read_points3(
+            "0,0,0 -> 9223372036854775807,0,0".as_bytes(),
+            "buffer",
+            LineMode::AxisAligned,
+        )
+        .unwrap_err();
+        match err {
+            ReadError::Parse(err) => assert_eq!(err.kind, ParseErrorKind::LineTooLong),
+            ReadError::Io(err) => panic!("unexpected I/O error: {}", err),
+        }
+
+        let (pairs, errors) = read_points3_lenient(
+            "0,0,0 -> 1,2,3\n1,1 -> 1,1,1\n0,0,0 -> 0,0,1".as_bytes(),
+            "buffer",
+            LineMode::WithDiagonals,
+        )
+        .unwrap();
+        assert_eq!(pairs.len(), 1);
+        assert_eq!(errors.len(), 2);
+        assert_eq!(errors[1].line, 2);
+    }
+
+    #[test]
+    fn test_volume_queries() {
+        let pairs = vec![
+            (c(0, 0, 0), c(4, 0, 0)),
+            (c(2, 0, 0), c(2, 0, 4)),
+            (c(0, 0, 0), c(4, 0, 4)),
+            (c(2, 0, 2), c(2, 0, 2)),
+        ];
+        let volume = Volume::new(&pairs, LineMode::AxisAligned);
+        assert_eq!(volume.sum_double_counts(), 2);
+        assert_eq!(volume.get_count(&c(2, 0, 0)).count, Count(2));
+
+        let volume = Volume::new(&pairs, LineMode::WithDiagonals);
+        assert_eq!(volume.sum_double_counts(), 3);
+        assert_eq!(volume.histogram(), vec![0, 9, 2, 1]);
+        assert_eq!(
+            volume.top_points(2),
+            vec![
+                PointCount3 {
+                    point: c(2, 0, 2),
+                    count: Count(3)
+                },
+                PointCount3 {
+                    point: c(0, 0, 0),
+                    count: Count(2)
+                },
+            ]
+        );
+        assert_eq!(volume.region_sum(&c(4, 0, 4), &c(0, 0, 0)), 16);
+        assert_eq!(volume.region_sum(&c(0, 1, 0), &c(4, 1, 4)), 0);
+    }
+}
@@ -0,0 +1,139 @@
+/// This file holds an unsigned integer of any size, for exact
+/// populations far past the range of u128.
+///
+/// Only what counting fish needs is implemented: addition and
+/// printing in decimal.
+use std::fmt;
+
+/// An unsigned integer stored as base 2^32 limbs, least significant
+/// first, without trailing zero limbs (so zero has no limbs).
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct BigUint {
+    limbs: Vec<u32>,
+}
+
+impl BigUint {
+    pub fn zero() -> Self {
+        BigUint::default()
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.limbs.is_empty()
+    }
+
+    /// Returns the number of bits needed to write the number.
+    pub fn bits(&self) -> u64 {
+        match self.limbs.last() {
+            Some(last) => self.limbs.len() as u64 * 32 - last.leading_zeros() as u64,
+            None => 0,
+        }
+    }
+
+    /// Returns the base 2 logarithm of the number, negative infinity for zero.
+    /// Only the two most significant limbs are used, which is plenty for an f64.
+    pub fn log2(&self) -> f64 {
+        let top = self.limbs.len().min(2);
+        let mantissa = self.limbs[self.limbs.len() - top..]
+            .iter()
+            .rev()
+            .fold(0.0, |value, &limb| value * 4294967296.0 + limb as f64);
+        mantissa.log2() + 32.0 * (self.limbs.len() - top) as f64
+    }
+
+    fn trim(&mut self) {
+        while self.limbs.last() == Some(&0) {
+            self.limbs.pop();
+        }
+    }
+
+    /// Divides the number in place and returns the remainder.
+    fn div_rem_small(&mut self, divisor: u32) -> u32 {
+        let mut remainder = 0u64;
+        for limb in self.limbs.iter_mut().rev() {
+            let value = (remainder << 32) | *limb as u64;
+            *limb = (value / divisor as u64) as u32;
+            remainder = value % divisor as u64;
+        }
+        self.trim();
+        remainder as u32
+    }
+}
+
+impl From<u128> for BigUint {
+    fn from(mut value: u128) -> Self {
+        let mut limbs = Vec::new();
+        while value > 0 {
+            limbs.push(value as u32);
+            value >>= 32;
+        }
+        BigUint { limbs }
+    }
+}
+
+impl std::ops::AddAssign<&BigUint> for BigUint {
+    fn add_assign(&mut self, other: &BigUint) {
+        if self.limbs.len() < other.limbs.len() {
+            self.limbs.resize(other.limbs.len(), 0);
+        }
+        let mut carry = 0u64;
+        for (index, limb) in self.limbs.iter_mut().enumerate() {
+            let sum = *limb as u64 + other.limbs.get(index).copied().unwrap_or(0) as u64 + carry;
+            *limb = sum as u32;
+            carry = sum >> 32;
+            if carry == 0 && index >= other.limbs.len() {
+                break;
+            }
+        }
+        if carry > 0 {
+            self.limbs.push(carry as u32);
+        }
+    }
+}
+
+impl fmt::Display for BigUint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_zero() {
+            return write!(f, "0");
+        }
+        // Split into groups of 9 decimal digits, least significant first.
+        let mut rest = self.clone();
+        let mut groups = Vec::new();
+        while !rest.is_zero() {
+            groups.push(rest.div_rem_small(1_000_000_000));
+        }
+        let mut groups = groups.iter().rev();
+        write!(f, "{}", groups.next().unwrap())?;
+        for group in groups {
+            write!(f, "{:09}", group)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_big_uint_add_and_display() {
+        assert_eq!(BigUint::zero().to_string(), "0");
+        assert_eq!(BigUint::from(1_000_000_000).to_string(), "1000000000");
+
+        let mut sum = BigUint::from(u128::MAX);
+        sum += &BigUint::from(1);
+        assert_eq!(sum.to_string(), "340282366920938463463374607431768211456");
+        assert_eq!(sum.bits(), 129);
+
+        let mut doubled = sum.clone();
+        doubled += &sum;
+        assert_eq!(
+            doubled.to_string(),
+            "680564733841876926926749214863536422912"
+        );
+        assert_eq!(BigUint::from(0).bits(), 0);
+
+        assert_eq!(BigUint::from(1 << 40).log2(), 40.0);
+        assert!((doubled.log2() - 129.0).abs() < 1e-9);
+        assert_eq!(BigUint::zero().log2(), f64::NEG_INFINITY);
+    }
+}
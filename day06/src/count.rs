@@ -0,0 +1,57 @@
+/// This file abstracts over the integer type used to count fish, so
+/// the same simulation can run with machine integers that report
+/// overflow, or with BigUint, which is exact for any day.
+use std::fmt;
+
+use crate::bigint::BigUint;
+
+/// A number of fish.
+/// `Default` is zero.
+pub trait Count: Clone + Default + PartialEq + fmt::Debug + fmt::Display {
+    /// Converts a count, or returns None if it does not fit.
+    fn from_usize(count: usize) -> Option<Self>;
+
+    /// Adds two counts, or returns None if the sum does not fit.
+    fn checked_add(&self, other: &Self) -> Option<Self>;
+
+    /// Returns the base 2 logarithm of the count, negative infinity for zero.
+    fn log2(&self) -> f64;
+}
+
+macro_rules! impl_count {
+    ($($type:ty),*) => {
+        $(
+            impl Count for $type {
+                fn from_usize(count: usize) -> Option<Self> {
+                    <$type>::try_from(count).ok()
+                }
+
+                fn checked_add(&self, other: &Self) -> Option<Self> {
+                    <$type>::checked_add(*self, *other)
+                }
+
+                fn log2(&self) -> f64 {
+                    (*self as f64).log2()
+                }
+            }
+        )*
+    };
+}
+
+impl_count!(u8, u16, u32, u64, u128, usize);
+
+impl Count for BigUint {
+    fn from_usize(count: usize) -> Option<Self> {
+        Some(BigUint::from(count as u128))
+    }
+
+    fn checked_add(&self, other: &Self) -> Option<Self> {
+        let mut sum = self.clone();
+        sum += other;
+        Some(sum)
+    }
+
+    fn log2(&self) -> f64 {
+        BigUint::log2(self)
+    }
+}
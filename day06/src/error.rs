@@ -0,0 +1,78 @@
+/// This file defines the errors reported while reading the initial fish.
+/// A ParseError points at a 1-based column of the comma-separated input,
+/// and a ReadError is either an I/O failure or a ParseError.
+use std::fmt;
+use std::io;
+use std::num::ParseIntError;
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum ParseErrorKind {
+    /// The input has no fish at all.
+    Empty,
+    /// There is nothing between two commas, or before or after one.
+    MissingTimer,
+    BadInteger(ParseIntError),
+    TimerOutOfRange {
+        timer: usize,
+        max_timer: usize,
+    },
+}
+
+impl fmt::Display for ParseErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseErrorKind::Empty => write!(f, "no fish in input"),
+            ParseErrorKind::MissingTimer => write!(f, "missing timer between commas"),
+            ParseErrorKind::BadInteger(err) => write!(f, "bad integer: {}", err),
+            ParseErrorKind::TimerOutOfRange { timer, max_timer } => write!(
+                f,
+                "timer {} is above the highest timer {}",
+                timer, max_timer
+            ),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct ParseError {
+    pub file: String,
+    pub column: usize,
+    pub kind: ParseErrorKind,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.file, self.column, self.kind)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+#[derive(Debug)]
+pub enum ReadError {
+    Io(io::Error),
+    Parse(ParseError),
+}
+
+impl fmt::Display for ReadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ReadError::Io(err) => write!(f, "{}", err),
+            ReadError::Parse(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl std::error::Error for ReadError {}
+
+impl From<io::Error> for ReadError {
+    fn from(err: io::Error) -> Self {
+        ReadError::Io(err)
+    }
+}
+
+impl From<ParseError> for ReadError {
+    fn from(err: ParseError) -> Self {
+        ReadError::Parse(err)
+    }
+}
@@ -0,0 +1,158 @@
+/// This file reads the initial school of fish: a single line of
+/// comma-separated timers such as `3,4,3,1,2`.
+///
+/// Spaces around a timer and a trailing newline are accepted, but
+/// every timer must be a non-negative integer no higher than the
+/// highest timer of the lifecycle.
+use std::fs::File;
+use std::io::{self, BufReader, Read};
+
+use crate::error::{ParseError, ParseErrorKind, ReadError};
+use crate::school::{FishSchool, Lifecycle};
+
+/// The path that stands for standard input.
+pub const STDIN_PATH: &str = "-";
+
+/// Parses the comma-separated timers of the fish.
+/// `name` is the file name used in errors.
+pub fn parse_timers(
+    input: &str,
+    name: &str,
+    lifecycle: &Lifecycle,
+) -> Result<Vec<usize>, ParseError> {
+    let error = |column: usize, kind: ParseErrorKind| ParseError {
+        file: name.to_string(),
+        column,
+        kind,
+    };
+    let max_timer = lifecycle.max_timer();
+    let input = input.trim_end();
+    if input.trim_start().is_empty() {
+        return Err(error(1, ParseErrorKind::Empty));
+    }
+
+    let mut timers = Vec::new();
+    let mut offset = 0;
+    for entry in input.split(',') {
+        let trimmed = entry.trim_start();
+        let column = offset + entry.len() - trimmed.len() + 1;
+        let trimmed = trimmed.trim_end();
+        if trimmed.is_empty() {
+            return Err(error(column, ParseErrorKind::MissingTimer));
+        }
+        let timer: usize = trimmed
+            .parse()
+            .map_err(|err| error(column, ParseErrorKind::BadInteger(err)))?;
+        if timer > max_timer {
+            return Err(error(
+                column,
+                ParseErrorKind::TimerOutOfRange { timer, max_timer },
+            ));
+        }
+        timers.push(timer);
+        offset += entry.len() + 1;
+    }
+    Ok(timers)
+}
+
+/// Reads a school of fish with the given lifecycle.
+pub fn read_school<R: Read>(
+    mut reader: R,
+    name: &str,
+    lifecycle: Lifecycle,
+) -> Result<FishSchool, ReadError> {
+    let mut input = String::new();
+    reader.read_to_string(&mut input)?;
+    let timers = parse_timers(&input, name, &lifecycle)?;
+    // The timers have been checked against the lifecycle already.
+    Ok(FishSchool::with_lifecycle(timers, lifecycle).expect("timers are in range"))
+}
+
+/// Opens a file for reading, or standard input when the path is `-`.
+pub fn open_input(path: &str) -> io::Result<Box<dyn Read>> {
+    if path == STDIN_PATH {
+        Ok(Box::new(io::stdin().lock()))
+    } else {
+        Ok(Box::new(BufReader::new(File::open(path)?)))
+    }
+}
+
+/// Reads a school of lanternfish from a file.
+pub fn read_file_to_school(path: &str) -> Result<FishSchool, ReadError> {
+    read_school(File::open(path)?, path, Lifecycle::default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(input: &str) -> Result<Vec<usize>, ParseError> {
+        parse_timers(input, "test", &Lifecycle::default())
+    }
+
+    fn kind_at(input: &str) -> (usize, ParseErrorKind) {
+        let err = parse(input).unwrap_err();
+        (err.column, err.kind)
+    }
+
+    #[test]
+    fn test_parse_timers() {
+        assert_eq!(parse("3,4,3,1,2"), Ok(vec![3, 4, 3, 1, 2]));
+        assert_eq!(parse("3, 4 ,3\n"), Ok(vec![3, 4, 3]));
+        assert_eq!(parse("8"), Ok(vec![8]));
+    }
+
+    #[test]
+    fn test_parse_timers_errors() {
+        assert_eq!(kind_at(""), (1, ParseErrorKind::Empty));
+        assert_eq!(kind_at(" \n"), (1, ParseErrorKind::Empty));
+        assert_eq!(kind_at("3,,4"), (3, ParseErrorKind::MissingTimer));
+        assert_eq!(kind_at("3,4,"), (5, ParseErrorKind::MissingTimer));
+        assert_eq!(
+            kind_at("3, 9"),
+            (
+                4,
+                ParseErrorKind::TimerOutOfRange {
+                    timer: 9,
+                    max_timer: 8
+                }
+            )
+        );
+        assert!(matches!(
+            kind_at("3,-1"),
+            (3, ParseErrorKind::BadInteger(_))
+        ));
+        assert!(matches!(
+            kind_at("3\n4"),
+            (1, ParseErrorKind::BadInteger(_))
+        ));
+
+        let lifecycle = Lifecycle {
+            reset_timer: 2,
+            newborn_timer: 4,
+        };
+        assert!(parse_timers("5", "test", &lifecycle).is_err());
+        assert_eq!(
+            parse_timers("3,4", "small.txt", &lifecycle).map(|timers| timers.len()),
+            Ok(2)
+        );
+        assert_eq!(
+            parse_timers("3,5", "small.txt", &lifecycle)
+                .unwrap_err()
+                .to_string(),
+            "small.txt:3: timer 5 is above the highest timer 4"
+        );
+    }
+
+    #[test]
+    fn test_read_file_to_school() {
+        let school = read_file_to_school("data/sample1.txt").unwrap();
+        assert_eq!(school.simulate_fishes(80), Ok(5934));
+        let school = read_file_to_school("data/data1.txt").unwrap();
+        assert_eq!(school.total(), 300);
+        assert!(matches!(
+            read_file_to_school("data/missing.txt"),
+            Err(ReadError::Io(_))
+        ));
+    }
+}
@@ -0,0 +1,199 @@
+/// This file exports the day by day history of a school, as yielded by
+/// `FishSchool::days`, for growth analysis.
+///
+/// The history can be written as CSV (`day,total,t0,t1,...` with one
+/// column per timer bucket) or as a JSON array, and drawn in the
+/// terminal as a sparkline or as a bar chart on a logarithmic scale,
+/// since the population grows exponentially.
+use std::io::{self, Write};
+
+use crate::count::Count;
+use crate::school::DayState;
+
+/// The sparkline symbols, from the smallest population to the largest.
+const LEVELS: &[u8] = b"_.-:=+*#";
+
+/// Writes the history as CSV with a `day,total,t0,t1,...` header.
+pub fn write_history_csv<W: Write, C: Count>(
+    writer: &mut W,
+    history: &[DayState<C>],
+) -> io::Result<()> {
+    write!(writer, "day,total")?;
+    let bucket_count = history.first().map_or(0, |state| state.buckets.len());
+    for timer in 0..bucket_count {
+        write!(writer, ",t{}", timer)?;
+    }
+    writeln!(writer)?;
+    for state in history {
+        write!(writer, "{},{}", state.day, state.total)?;
+        for count in &state.buckets {
+            write!(writer, ",{}", count)?;
+        }
+        writeln!(writer)?;
+    }
+    writer.flush()
+}
+
+/// Writes the history as a JSON array of `{"day", "total", "buckets"}` objects.
+/// Counts are written as JSON numbers with every digit, even past u64.
+pub fn write_history_json<W: Write, C: Count>(
+    writer: &mut W,
+    history: &[DayState<C>],
+) -> io::Result<()> {
+    write!(writer, "[")?;
+    for (index, state) in history.iter().enumerate() {
+        if index > 0 {
+            write!(writer, ",")?;
+        }
+        write!(
+            writer,
+            r#"{{"day":{},"total":{},"buckets":["#,
+            state.day, state.total
+        )?;
+        for (timer, count) in state.buckets.iter().enumerate() {
+            if timer > 0 {
+                write!(writer, ",")?;
+            }
+            write!(writer, "{}", count)?;
+        }
+        write!(writer, "]}}")?;
+    }
+    writeln!(writer, "]")?;
+    writer.flush()
+}
+
+/// Returns the range of the base 2 logarithms of the totals that are not zero.
+fn log_range<C: Count>(history: &[DayState<C>]) -> Option<(f64, f64)> {
+    history
+        .iter()
+        .map(|state| state.total.log2())
+        .filter(|log| log.is_finite())
+        .fold(None, |range, log| match range {
+            None => Some((log, log)),
+            Some((min, max)) => Some((f64::min(min, log), f64::max(max, log))),
+        })
+}
+
+/// Draws the totals as one symbol per day, on a logarithmic scale
+/// between the smallest and the largest total. Days without fish are spaces.
+pub fn sparkline<C: Count>(history: &[DayState<C>]) -> String {
+    let Some((min, max)) = log_range(history) else {
+        return " ".repeat(history.len());
+    };
+    let top = (LEVELS.len() - 1) as f64;
+    history
+        .iter()
+        .map(|state| {
+            let log = state.total.log2();
+            if !log.is_finite() {
+                ' '
+            } else if max == min {
+                LEVELS[LEVELS.len() - 1] as char
+            } else {
+                LEVELS[((log - min) / (max - min) * top).round() as usize] as char
+            }
+        })
+        .collect()
+}
+
+/// Writes one line per day with a bar whose length is proportional to
+/// the logarithm of the total, up to `width` characters for the largest.
+pub fn write_log_chart<W: Write, C: Count>(
+    writer: &mut W,
+    history: &[DayState<C>],
+    width: usize,
+) -> io::Result<()> {
+    let max = log_range(history).map_or(0.0, |(_, max)| max);
+    let day_width = history
+        .last()
+        .map_or(1, |state| state.day.to_string().len());
+    for state in history {
+        let log = state.total.log2();
+        // A single fish has a logarithm of 0, so count from 1 to show it.
+        let length = if !log.is_finite() {
+            0
+        } else {
+            ((log + 1.0) / (max + 1.0) * width as f64).round() as usize
+        };
+        writeln!(
+            writer,
+            "{:>day_width$} |{:<width$}| {}",
+            state.day,
+            "#".repeat(length),
+            state.total,
+            day_width = day_width,
+            width = width
+        )?;
+    }
+    writer.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bigint::BigUint;
+    use crate::school::FishSchool;
+
+    fn sample_history(days: usize) -> Vec<DayState<usize>> {
+        let school = FishSchool::new(vec![3, 4, 3, 1, 2]).unwrap();
+        school.days().take(days + 1).collect()
+    }
+
+    #[test]
+    fn test_write_history_csv() {
+        let mut output = Vec::new();
+        write_history_csv(&mut output, &sample_history(2)).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "day,total,t0,t1,t2,t3,t4,t5,t6,t7,t8\n\
+             0,5,0,1,1,2,1,0,0,0,0\n\
+             1,5,1,1,2,1,0,0,0,0,0\n\
+             2,6,1,2,1,0,0,0,1,0,1\n"
+        );
+    }
+
+    #[test]
+    fn test_write_history_json() {
+        let mut output = Vec::new();
+        write_history_json(&mut output, &sample_history(1)).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "[{\"day\":0,\"total\":5,\"buckets\":[0,1,1,2,1,0,0,0,0]},\
+             {\"day\":1,\"total\":5,\"buckets\":[1,1,2,1,0,0,0,0,0]}]\n"
+        );
+
+        let school = FishSchool::new(vec![0]).unwrap();
+        let history: Vec<DayState<BigUint>> = school.days_as().skip(1000).take(1).collect();
+        let mut output = Vec::new();
+        write_history_json(&mut output, &history).unwrap();
+        let json = String::from_utf8(output).unwrap();
+        let total = history[0].total.to_string();
+        assert!(total.len() > 30);
+        assert!(json.contains(&format!("\"total\":{},", total)));
+    }
+
+    #[test]
+    fn test_sparkline() {
+        let line = sparkline(&sample_history(18));
+        assert_eq!(line.len(), 19);
+        assert!(line.starts_with('_'));
+        assert!(line.ends_with('#'));
+        assert_eq!(sparkline::<usize>(&[]), "");
+
+        let empty = FishSchool::new(vec![]).unwrap();
+        let history: Vec<DayState<usize>> = empty.days().take(3).collect();
+        assert_eq!(sparkline(&history), "   ");
+    }
+
+    #[test]
+    fn test_write_log_chart() {
+        let school = FishSchool::new(vec![0]).unwrap();
+        let history: Vec<DayState<usize>> = school.days().take(2).collect();
+        let mut output = Vec::new();
+        write_log_chart(&mut output, &history, 4).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "0 |##  | 1\n1 |####| 2\n"
+        );
+    }
+}
@@ -0,0 +1,8 @@
+pub mod bigint;
+pub mod count;
+pub mod error;
+pub mod file;
+pub mod history;
+pub mod matrix;
+pub mod school;
+pub mod stochastic;
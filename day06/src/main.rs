@@ -1,61 +1,134 @@
-/// Represents a school of fish.
-/// Each fish has an internal timer which determines its state.
-/// When a fish's timer reaches 0, it creates a new fish with an internal timer of 8.
-/// All fish are contained in a vector.
-struct FishSchool {
-    fish: Vec<usize>,
- }
- 
- impl FishSchool {
-    /// Creates a new FishSchool with the given initial fish.
-    fn new(initial_fish: Vec<usize>) -> Self {
-        FishSchool { fish: initial_fish }
+use std::env;
+use std::io;
+use std::process;
+
+use day06::file::{open_input, read_school, STDIN_PATH};
+use day06::history::{sparkline, write_history_csv, write_history_json, write_log_chart};
+use day06::school::{DayState, Lifecycle};
+
+const DEFAULT_PATH: &str = "data/data1.txt";
+
+/// The width of the bars drawn by `--chart`.
+const CHART_WIDTH: usize = 60;
+
+fn parse_day(arg: &str) -> usize {
+    arg.parse().unwrap_or_else(|err| {
+        eprintln!("bad day count '{}': {}", arg, err);
+        process::exit(2);
+    })
+}
+
+/// Usage: day06 [PATH [DAYS... | --csv DAY | --json DAY | --chart DAY | --reach TARGET]]
+/// Reads the initial fish from PATH, or from standard input when PATH is `-`.
+/// Prints the answers to both parts, or the number of fish after each of DAYS,
+/// or the history of the school from day 0 to DAY as CSV, JSON or a chart,
+/// or the first day with at least TARGET fish.
+/// Exits with status 1 if a population overflows, after printing the rest.
+fn main() {
+    let mut args = env::args().skip(1);
+    let path = args.next().unwrap_or_else(|| DEFAULT_PATH.to_string());
+    let args: Vec<String> = args.collect();
+    let format = args.first().filter(|arg| arg.starts_with("--")).cloned();
+    let values = &args[format.is_some() as usize..];
+
+    let name = if path == STDIN_PATH { "<stdin>" } else { &path };
+    let reader = open_input(&path).unwrap_or_else(|err| {
+        eprintln!("{}: {}", name, err);
+        process::exit(1);
+    });
+    let mut fish = read_school(reader, name, Lifecycle::default()).unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        process::exit(1);
+    });
+
+    if format.as_deref() == Some("--reach") {
+        let [target] = values else {
+            eprintln!("--reach takes exactly one number of fish");
+            process::exit(2);
+        };
+        let target: u128 = target.parse().unwrap_or_else(|err| {
+            eprintln!("bad number of fish '{}': {}", target, err);
+            process::exit(2);
+        });
+        match fish.first_day_reaching(target) {
+            Some(day) => println!("Day {}: at least {} fish", day, target),
+            None => println!("The school never reaches {} fish", target),
+        }
+        return;
+    }
+
+    let days: Vec<usize> = values.iter().map(|arg| parse_day(arg)).collect();
+    if let Some(format) = format {
+        let [last_day] = days[..] else {
+            eprintln!("{} takes exactly one day count", format);
+            process::exit(2);
+        };
+        let history: Vec<DayState<usize>> = fish.days().take(last_day.saturating_add(1)).collect();
+        let mut stdout = io::stdout().lock();
+        let result = match format.as_str() {
+            "--csv" => write_history_csv(&mut stdout, &history),
+            "--json" => write_history_json(&mut stdout, &history),
+            "--chart" => {
+                println!("{}", sparkline(&history));
+                write_log_chart(&mut stdout, &history, CHART_WIDTH)
+            }
+            _ => {
+                eprintln!("unknown option '{}'", format);
+                process::exit(2);
+            }
+        };
+        if let Err(err) = result {
+            eprintln!("{}", err);
+            process::exit(1);
+        }
+        if history.len() <= last_day {
+            eprintln!("the population overflows on day {}", history.len());
+            process::exit(1);
+        }
+        return;
     }
- 
-    /// Simulates the growth of the fish school over a certain number of days.
-    /// Returns the total number of fish after the simulation.
-    fn simulate_fishes(&mut self, days: usize) -> usize {
-        // Create a map to count the number of fish at each stage of their lifecycle.
-        // Index 0 represents fish with a timer of 0, index 1 represents fish with a timer of 1, etc.
-        let mut map = [0; 9];
-        // Count the number of fish and their life cycle at the start.
-        // It does this by incrementing the corresponding index in the map array for each fish. 
-        // For instance, if the fish vector is:
-        //      [3, 4, 3, 1, 2], 
-        // after this loop, the map array would look like this: 
-        //      [0, 0, 0, 2, 1, 1, 0, 0, 0]. 
-        // This means there are 2 fish with a timer of 3, 1 fish with a timer of 4, and 1 fish with a timer of 1.
-        for &fish in &self.fish {
-            map[fish] += 1;
+
+    let mut overflowed = false;
+    if days.is_empty() {
+        // Part 2 resumes from day 80.
+        for (part, day) in [(1, 80), (2, 256)] {
+            match fish.advance(day - fish.day()) {
+                Ok(count) => println!("Part {}: {}", part, count),
+                Err(err) => {
+                    eprintln!("Part {}: {}", part, err);
+                    overflowed = true;
+                }
+            }
         }
- 
-        // Simulate each day.
-        for _ in 1..days {
-            // Each day, all fish with a timer of 0 create a new fish with a timer of 8.
-            // These new fish are added to the map at index 8.
-            // say after some time, we have a usize array [1,0,0,0,0,0,0,0,0]. 
-            // Here, there is 1 fish with a timer of 0. So, map[0] would be 1. 
-            // The line map[8] += map[0]; now adds the value of map[0] (which is 1) 
-            // to map[8]. So, the array becomes [1,0,0,0,0,0,0,0,1]. 
-            // This means that 1 new fish with a timer of 8 was created.
-            map[8] += map[0];
-            // Then, all fish move forward one stage in their lifecycle.
-            // This is done by shifting all elements in the map one position to the left,
-            // wrapping around from index 0 to index 8.
-            map.rotate_left(1);
+        if overflowed {
+            process::exit(1);
         }
- 
-        // Sum up the counts in the map to get the total number of fish.
-        map.iter().sum()
+        return;
+    }
+
+    // Advance through the days in order so the simulation runs once,
+    // then report them in the order they were asked for.
+    let mut sorted = days.clone();
+    sorted.sort_unstable();
+    sorted.dedup();
+    let counts: Vec<_> = sorted
+        .iter()
+        .map(|&day| (day, fish.advance(day - fish.day())))
+        .collect();
+    for day in days {
+        let (_, count) = counts
+            .iter()
+            .find(|(sorted_day, _)| *sorted_day == day)
+            .unwrap();
+        match count {
+            Ok(count) => println!("Day {}: {}", day, count),
+            Err(err) => {
+                eprintln!("Day {}: {}", day, err);
+                overflowed = true;
+            }
+        }
+    }
+    if overflowed {
+        process::exit(1);
     }
- }
- 
- fn main() {
-    let mut fish = FishSchool::new(vec![
-        // Initial fish population...
-    ]);
-    // part 1
-    println!("Part 1: {:?}", fish.simulate_fishes(80));
-    // part 2
-    println!("Part 2: {:?}", fish.simulate_fishes(256))
- }
+}
@@ -0,0 +1,260 @@
+/// This file answers the population of a school for any day in
+/// logarithmic time, modulo a number.
+///
+/// One day of the lifecycle is a linear map of the timer buckets, so
+/// it can be written as a square matrix M with `new = M * old`.
+/// The buckets after `days` days are then `M^days * initial`, and
+/// M^days is computed by repeated squaring in O(n^3 log days).
+///
+/// SaturatingMatrix uses the same representation with exact u128
+/// entries that stop at u128::MAX, for comparing populations with a
+/// threshold rather than computing them.
+use crate::school::Lifecycle;
+
+/// A square matrix whose entries are reduced modulo `modulus`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct TransitionMatrix {
+    size: usize,
+    modulus: u64,
+    entries: Vec<u64>,
+}
+
+impl TransitionMatrix {
+    fn identity(size: usize, modulus: u64) -> Self {
+        let mut entries = vec![0; size * size];
+        for i in 0..size {
+            entries[i * size + i] = 1 % modulus;
+        }
+        TransitionMatrix {
+            size,
+            modulus,
+            entries,
+        }
+    }
+
+    /// Builds the matrix of one day of the lifecycle.
+    /// Entry (row, column) is the number of fish that bucket `column`
+    /// sends to bucket `row`.
+    ///
+    /// Panics if the modulus is 0.
+    pub fn for_lifecycle(lifecycle: &Lifecycle, modulus: u64) -> Self {
+        assert!(modulus > 0, "the modulus must be positive");
+        let size = lifecycle.max_timer() + 1;
+        let mut matrix = TransitionMatrix {
+            size,
+            modulus,
+            entries: vec![0; size * size],
+        };
+        for timer in 1..size {
+            matrix.add(timer - 1, timer, 1);
+        }
+        matrix.add(lifecycle.reset_timer, 0, 1);
+        matrix.add(lifecycle.newborn_timer, 0, 1);
+        matrix
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    pub fn get(&self, row: usize, column: usize) -> u64 {
+        self.entries[row * self.size + column]
+    }
+
+    fn add(&mut self, row: usize, column: usize, value: u64) {
+        let entry = &mut self.entries[row * self.size + column];
+        *entry = ((*entry as u128 + value as u128) % self.modulus as u128) as u64;
+    }
+
+    fn multiply(&self, other: &Self) -> Self {
+        let size = self.size;
+        let mut result = TransitionMatrix {
+            size,
+            modulus: self.modulus,
+            entries: vec![0; size * size],
+        };
+        for row in 0..size {
+            for k in 0..size {
+                let left = self.get(row, k) as u128;
+                if left == 0 {
+                    continue;
+                }
+                for column in 0..size {
+                    let product = left * other.get(k, column) as u128 % self.modulus as u128;
+                    result.add(row, column, product as u64);
+                }
+            }
+        }
+        result
+    }
+
+    /// Raises the matrix to the given power by repeated squaring.
+    pub fn pow(&self, mut exponent: u64) -> Self {
+        let mut result = Self::identity(self.size, self.modulus);
+        let mut base = self.clone();
+        while exponent > 0 {
+            if exponent & 1 == 1 {
+                result = result.multiply(&base);
+            }
+            exponent >>= 1;
+            if exponent > 0 {
+                base = base.multiply(&base);
+            }
+        }
+        result
+    }
+
+    /// Applies the matrix to a vector of buckets.
+    ///
+    /// Panics if the number of buckets does not match the size of the matrix.
+    pub fn apply(&self, buckets: &[u64]) -> Vec<u64> {
+        assert_eq!(buckets.len(), self.size, "wrong number of buckets");
+        (0..self.size)
+            .map(|row| {
+                buckets
+                    .iter()
+                    .enumerate()
+                    .map(|(column, &count)| {
+                        self.get(row, column) as u128 * (count % self.modulus) as u128
+                    })
+                    .fold(0, |sum, product| (sum + product) % self.modulus as u128)
+                    as u64
+            })
+            .collect()
+    }
+}
+
+/// A square matrix of u128 entries whose arithmetic saturates.
+///
+/// Every entry is non-negative, so a saturated entry stays saturated
+/// through products with a non-zero entry and through sums. A result
+/// below u128::MAX is therefore exact, and a saturated one means the
+/// exact value is at least u128::MAX.
+#[derive(Debug, PartialEq, Clone)]
+pub struct SaturatingMatrix {
+    size: usize,
+    entries: Vec<u128>,
+}
+
+impl SaturatingMatrix {
+    /// Builds the matrix of one day of the lifecycle, like TransitionMatrix.
+    pub fn for_lifecycle(lifecycle: &Lifecycle) -> Self {
+        let size = lifecycle.max_timer() + 1;
+        let mut entries = vec![0; size * size];
+        for timer in 1..size {
+            entries[(timer - 1) * size + timer] += 1;
+        }
+        entries[lifecycle.reset_timer * size] += 1;
+        entries[lifecycle.newborn_timer * size] += 1;
+        SaturatingMatrix { size, entries }
+    }
+
+    pub fn get(&self, row: usize, column: usize) -> u128 {
+        self.entries[row * self.size + column]
+    }
+
+    fn multiply(&self, other: &Self) -> Self {
+        let size = self.size;
+        let mut entries = vec![0u128; size * size];
+        for row in 0..size {
+            for k in 0..size {
+                let left = self.get(row, k);
+                if left == 0 {
+                    continue;
+                }
+                for column in 0..size {
+                    let entry = &mut entries[row * size + column];
+                    *entry = entry.saturating_add(left.saturating_mul(other.get(k, column)));
+                }
+            }
+        }
+        SaturatingMatrix { size, entries }
+    }
+
+    /// Returns the matrix of twice as many days.
+    pub fn squared(&self) -> Self {
+        self.multiply(self)
+    }
+
+    /// Applies the matrix to a vector of buckets.
+    ///
+    /// Panics if the number of buckets does not match the size of the matrix.
+    pub fn apply(&self, buckets: &[u128]) -> Vec<u128> {
+        assert_eq!(buckets.len(), self.size, "wrong number of buckets");
+        (0..self.size)
+            .map(|row| {
+                buckets
+                    .iter()
+                    .enumerate()
+                    .fold(0u128, |sum, (column, &count)| {
+                        sum.saturating_add(self.get(row, column).saturating_mul(count))
+                    })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matrix_matches_step() {
+        for lifecycle in [
+            Lifecycle::default(),
+            Lifecycle {
+                reset_timer: 2,
+                newborn_timer: 4,
+            },
+            Lifecycle {
+                reset_timer: 3,
+                newborn_timer: 1,
+            },
+        ] {
+            let size = lifecycle.max_timer() + 1;
+            let mut buckets: Vec<usize> = (0..size).map(|timer| timer % 3).collect();
+            let initial: Vec<u64> = buckets.iter().map(|&count| count as u64).collect();
+            let matrix = TransitionMatrix::for_lifecycle(&lifecycle, u64::MAX);
+            for days in 0..40 {
+                let expected: Vec<u64> = buckets.iter().map(|&count| count as u64).collect();
+                assert_eq!(matrix.pow(days).apply(&initial), expected);
+                lifecycle.step(&mut buckets).unwrap();
+            }
+        }
+    }
+
+    #[test]
+    fn test_saturating_matrix() {
+        let lifecycle = Lifecycle::default();
+        let mut buckets: Vec<u128> = vec![0, 1, 1, 2, 1, 0, 0, 0, 0];
+        let mut matrix = SaturatingMatrix::for_lifecycle(&lifecycle);
+        // Two squarings give the matrix of four days.
+        let mut expected = buckets.clone();
+        for _ in 0..4 {
+            lifecycle.step(&mut expected).unwrap();
+        }
+        matrix = matrix.squared().squared();
+        assert_eq!(matrix.apply(&buckets), expected);
+
+        // Far enough, the counts saturate instead of wrapping around.
+        for _ in 0..12 {
+            matrix = matrix.squared();
+            buckets = matrix.apply(&buckets);
+        }
+        assert!(buckets.iter().all(|&count| count == u128::MAX));
+    }
+
+    #[test]
+    fn test_matrix_modulus() {
+        let matrix = TransitionMatrix::for_lifecycle(&Lifecycle::default(), 7);
+        assert_eq!(matrix.get(6, 0), 1);
+        assert_eq!(matrix.get(8, 0), 1);
+        assert_eq!(matrix.get(0, 1), 1);
+        assert_eq!(matrix.pow(0), TransitionMatrix::identity(9, 7));
+
+        // 5934 fish after 80 days in the sample.
+        let buckets = [0, 1, 1, 2, 1, 0, 0, 0, 0];
+        let total: u64 = matrix.pow(80).apply(&buckets).iter().sum();
+        assert_eq!(total % 7, 5934 % 7);
+    }
+}
@@ -0,0 +1,581 @@
+use std::fmt;
+
+use crate::count::Count;
+use crate::matrix::{SaturatingMatrix, TransitionMatrix};
+
+/// The timers that drive the lifecycle of a species of fish.
+/// A fish whose timer is 0 creates a new fish with a timer of
+/// `newborn_timer` and restarts its own timer at `reset_timer`.
+/// Lanternfish reset to 6 and are born at 8.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Lifecycle {
+    pub reset_timer: usize,
+    pub newborn_timer: usize,
+}
+
+impl Default for Lifecycle {
+    fn default() -> Self {
+        Lifecycle {
+            reset_timer: 6,
+            newborn_timer: 8,
+        }
+    }
+}
+
+impl Lifecycle {
+    /// The highest timer a fish can have, so there are
+    /// `max_timer() + 1` buckets of fish.
+    pub fn max_timer(&self) -> usize {
+        self.reset_timer.max(self.newborn_timer)
+    }
+
+    /// Advances the buckets by one day.
+    /// Every fish moves one bucket down, the fish at 0 move to the
+    /// reset bucket and create as many fish in the newborn bucket.
+    ///
+    /// Returns None if a bucket overflows, leaving the buckets in an
+    /// unspecified state.
+    pub fn step<C: Count>(&self, buckets: &mut [C]) -> Option<()> {
+        let spawning = std::mem::take(&mut buckets[0]);
+        buckets.rotate_left(1);
+        buckets[self.reset_timer] = buckets[self.reset_timer].checked_add(&spawning)?;
+        buckets[self.newborn_timer] = buckets[self.newborn_timer].checked_add(&spawning)?;
+        Some(())
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum SchoolError {
+    /// The fish at `index` has a timer above the highest timer of the lifecycle.
+    TimerOutOfRange {
+        index: usize,
+        timer: usize,
+        max_timer: usize,
+    },
+    /// The population no longer fits the count type on this day, or the
+    /// day itself does not fit in a usize (then `day` is `usize::MAX`).
+    Overflow { day: usize },
+    /// The parameters of a stochastic model are not usable.
+    InvalidModel(&'static str),
+}
+
+impl fmt::Display for SchoolError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SchoolError::TimerOutOfRange {
+                index,
+                timer,
+                max_timer,
+            } => write!(
+                f,
+                "fish {} has timer {}, the highest timer is {}",
+                index, timer, max_timer
+            ),
+            SchoolError::Overflow { day } => {
+                write!(f, "the population overflows on day {}", day)
+            }
+            SchoolError::InvalidModel(reason) => write!(f, "invalid model: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for SchoolError {}
+
+/// The state of a school on a given day, as yielded by `FishSchool::days`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct DayState<C> {
+    pub day: usize,
+    /// The number of fish for each timer, from 0 to the highest timer.
+    pub buckets: Vec<C>,
+    pub total: C,
+}
+
+/// Returns the sum of the buckets, or None if it does not fit the count type.
+fn total_of<C: Count>(buckets: &[C]) -> Option<C> {
+    buckets
+        .iter()
+        .try_fold(C::default(), |sum, count| sum.checked_add(count))
+}
+
+/// Iterates over the states of a school day by day, starting with the
+/// current day of the school. Stops before the first day whose
+/// population does not fit the count type.
+pub struct Days<C> {
+    lifecycle: Lifecycle,
+    buckets: Vec<C>,
+    day: usize,
+    overflowed: bool,
+}
+
+impl<C: Count> Iterator for Days<C> {
+    type Item = DayState<C>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.overflowed {
+            return None;
+        }
+        let Some(total) = total_of(&self.buckets) else {
+            self.overflowed = true;
+            return None;
+        };
+        let state = DayState {
+            day: self.day,
+            buckets: self.buckets.clone(),
+            total,
+        };
+        match self.day.checked_add(1) {
+            Some(day) => {
+                self.day = day;
+                self.overflowed = self.lifecycle.step(&mut self.buckets).is_none();
+            }
+            None => self.overflowed = true,
+        }
+        Some(state)
+    }
+}
+
+/// Represents a school of fish.
+/// Each fish has an internal timer which determines its state.
+/// When a fish's timer reaches 0, it creates a new fish with an internal timer of
+/// `newborn_timer` and its own timer restarts at `reset_timer` (8 and 6 by default).
+/// The fish are counted by timer, and the school remembers how many days
+/// it has been advanced, so a simulation can be resumed later.
+#[derive(Debug, PartialEq, Clone)]
+pub struct FishSchool {
+    buckets: Vec<usize>,
+    day: usize,
+    lifecycle: Lifecycle,
+}
+
+impl FishSchool {
+    /// Creates a new FishSchool of lanternfish with the given initial fish.
+    pub fn new(initial_fish: Vec<usize>) -> Result<Self, SchoolError> {
+        Self::with_lifecycle(initial_fish, Lifecycle::default())
+    }
+
+    /// Creates a new FishSchool of a species with the given lifecycle,
+    /// checking that every timer is valid for it.
+    pub fn with_lifecycle(
+        initial_fish: Vec<usize>,
+        lifecycle: Lifecycle,
+    ) -> Result<Self, SchoolError> {
+        let max_timer = lifecycle.max_timer();
+        // Count the number of fish at each stage of their lifecycle.
+        // Index 0 represents fish with a timer of 0, index 1 represents fish with a timer of 1, etc.
+        // For instance, if the fish vector is:
+        //      [3, 4, 3, 1, 2],
+        // the buckets would look like this:
+        //      [0, 1, 1, 2, 1, 0, 0, 0, 0].
+        // This means there are 2 fish with a timer of 3, 1 fish with a timer of 4, and so on.
+        let mut buckets = vec![0; max_timer + 1];
+        for (index, &timer) in initial_fish.iter().enumerate() {
+            if timer > max_timer {
+                return Err(SchoolError::TimerOutOfRange {
+                    index,
+                    timer,
+                    max_timer,
+                });
+            }
+            buckets[timer] += 1;
+        }
+        Ok(FishSchool {
+            buckets,
+            day: 0,
+            lifecycle,
+        })
+    }
+
+    pub fn lifecycle(&self) -> Lifecycle {
+        self.lifecycle
+    }
+
+    /// Returns the number of days the school has been advanced.
+    pub fn day(&self) -> usize {
+        self.day
+    }
+
+    /// Returns the number of fish for each timer, from 0 to the highest timer.
+    pub fn buckets(&self) -> &[usize] {
+        &self.buckets
+    }
+
+    /// Returns the number of fish today.
+    pub fn total(&self) -> usize {
+        self.buckets.iter().sum()
+    }
+
+    /// Returns the number of fish `days` days from now modulo `modulus`,
+    /// in logarithmic time. See TransitionMatrix.
+    ///
+    /// Panics if the modulus is 0.
+    pub fn population_mod(&self, days: u64, modulus: u64) -> u64 {
+        let buckets: Vec<u64> = self.buckets.iter().map(|&count| count as u64).collect();
+        TransitionMatrix::for_lifecycle(&self.lifecycle, modulus)
+            .pow(days)
+            .apply(&buckets)
+            .iter()
+            .fold(0, |sum, &count| {
+                ((sum as u128 + count as u128) % modulus as u128) as u64
+            })
+    }
+
+    /// Iterates over the states of the school from today on.
+    pub fn days(&self) -> Days<usize> {
+        self.days_as()
+    }
+
+    /// Iterates over the states of the school from today on, counting with the given type.
+    /// Yields nothing if the school today does not fit the count type.
+    pub fn days_as<C: Count>(&self) -> Days<C> {
+        let buckets: Option<Vec<C>> = self
+            .buckets
+            .iter()
+            .map(|&count| C::from_usize(count))
+            .collect();
+        Days {
+            lifecycle: self.lifecycle,
+            overflowed: buckets.is_none(),
+            buckets: buckets.unwrap_or_default(),
+            day: self.day,
+        }
+    }
+
+    /// Advances the school by a certain number of days and returns the
+    /// number of fish on the new day. On overflow the school is left unchanged.
+    pub fn advance(&mut self, days: usize) -> Result<usize, SchoolError> {
+        let state = self.state_after::<usize>(days)?;
+        self.buckets = state.buckets;
+        self.day = state.day;
+        Ok(state.total)
+    }
+
+    /// Returns the state of the school a certain number of days from today,
+    /// or the first day whose population does not fit the count type.
+    /// An empty school stays empty, so it is not stepped at all.
+    fn state_after<C: Count>(&self, days: usize) -> Result<DayState<C>, SchoolError> {
+        let overflow = |day| SchoolError::Overflow { day };
+        let end = self.day.checked_add(days).ok_or(overflow(usize::MAX))?;
+        let mut buckets: Vec<C> = self
+            .buckets
+            .iter()
+            .map(|&count| C::from_usize(count))
+            .collect::<Option<_>>()
+            .ok_or(overflow(self.day))?;
+        let mut total = total_of(&buckets).ok_or(overflow(self.day))?;
+        let mut day = self.day;
+        while day < end && total != C::default() {
+            day += 1;
+            self.lifecycle.step(&mut buckets).ok_or(overflow(day))?;
+            total = total_of(&buckets).ok_or(overflow(day))?;
+        }
+        Ok(DayState {
+            day: end,
+            buckets,
+            total,
+        })
+    }
+
+    /// Simulates the growth of the fish school over a certain number of days
+    /// from today, without advancing it.
+    /// Returns the total number of fish after the simulation, or an error
+    /// if it does not fit in a usize.
+    pub fn simulate_fishes(&self, days: usize) -> Result<usize, SchoolError> {
+        self.simulate_fishes_as(days)
+    }
+
+    /// Simulates the growth of the fish school, counting with the given type.
+    /// Use u128 to go further than usize, or BigUint for an exact count on any day.
+    pub fn simulate_fishes_as<C: Count>(&self, days: usize) -> Result<C, SchoolError> {
+        self.state_after(days).map(|state| state.total)
+    }
+
+    /// Returns the first day, from today on, on which there are at least
+    /// `target` fish, or None if the school is empty and never grows.
+    ///
+    /// Instead of stepping one day at a time, this gallops: the matrices
+    /// of 1, 2, 4, ... days are squared until one of them reaches the
+    /// target, then the day is found by binary search over those matrices,
+    /// so it takes a logarithmic number of matrix products.
+    pub fn first_day_reaching(&self, target: u128) -> Option<usize> {
+        let total = |buckets: &[u128]| {
+            buckets
+                .iter()
+                .fold(0u128, |sum, &count| sum.saturating_add(count))
+        };
+        let mut buckets: Vec<u128> = self.buckets.iter().map(|&count| count as u128).collect();
+        if total(&buckets) >= target {
+            return Some(self.day);
+        }
+        if total(&buckets) == 0 {
+            return None;
+        }
+
+        // powers[k] is the matrix of 2^k days.
+        let mut powers = vec![SaturatingMatrix::for_lifecycle(&self.lifecycle)];
+        while total(&powers[powers.len() - 1].apply(&buckets)) < target {
+            let next = powers[powers.len() - 1].squared();
+            powers.push(next);
+        }
+
+        // The target is reached within 2^k days: take every jump that
+        // stays below the target, and the day after is the answer.
+        let mut days = 0;
+        for (k, power) in powers.iter().enumerate().rev().skip(1) {
+            let next = power.apply(&buckets);
+            if total(&next) < target {
+                buckets = next;
+                days += 1 << k;
+            }
+        }
+        Some(self.day + days + 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bigint::BigUint;
+
+    #[test]
+    fn test_lifecycle_step_lanternfish() {
+        let lifecycle = Lifecycle::default();
+        let mut buckets: Vec<usize> = vec![2, 0, 0, 0, 0, 0, 0, 1, 3];
+        lifecycle.step(&mut buckets).unwrap();
+        assert_eq!(buckets, vec![0, 0, 0, 0, 0, 0, 3, 3, 2]);
+    }
+
+    #[test]
+    fn test_lifecycle_step_other_species() {
+        // Resets to 2 and is born at 4: the buckets hold timers 0 to 4.
+        let lifecycle = Lifecycle {
+            reset_timer: 2,
+            newborn_timer: 4,
+        };
+        let mut buckets: Vec<usize> = vec![1, 0, 0, 0, 0];
+        let mut totals = Vec::new();
+        for _ in 0..6 {
+            lifecycle.step(&mut buckets).unwrap();
+            totals.push(buckets.iter().sum::<usize>());
+        }
+        // A fish spawns after 1, 4, 7, ... days, its first child after 1 + 5 days.
+        assert_eq!(totals, vec![2, 2, 2, 3, 3, 4]);
+
+        // The newborn timer may also be below the reset timer.
+        let lifecycle = Lifecycle {
+            reset_timer: 3,
+            newborn_timer: 1,
+        };
+        let mut buckets: Vec<usize> = vec![1, 0, 0, 0];
+        lifecycle.step(&mut buckets).unwrap();
+        assert_eq!(buckets, vec![0, 1, 0, 1]);
+    }
+
+    #[test]
+    fn test_fish_school_validates_timers() {
+        assert!(FishSchool::new(vec![3, 4, 3, 1, 2]).is_ok());
+        assert_eq!(
+            FishSchool::new(vec![3, 9]).err(),
+            Some(SchoolError::TimerOutOfRange {
+                index: 1,
+                timer: 9,
+                max_timer: 8
+            })
+        );
+
+        let lifecycle = Lifecycle {
+            reset_timer: 2,
+            newborn_timer: 4,
+        };
+        assert!(FishSchool::with_lifecycle(vec![4, 0], lifecycle).is_ok());
+        assert!(FishSchool::with_lifecycle(vec![5], lifecycle).is_err());
+    }
+
+    #[test]
+    fn test_fish_school_default_lifecycle() {
+        let default = FishSchool::new(vec![3, 4, 3, 1, 2]).unwrap();
+        let explicit = FishSchool::with_lifecycle(
+            vec![3, 4, 3, 1, 2],
+            Lifecycle {
+                reset_timer: 6,
+                newborn_timer: 8,
+            },
+        )
+        .unwrap();
+        assert_eq!(default.simulate_fishes(80), explicit.simulate_fishes(80));
+    }
+
+    #[test]
+    fn test_simulate_fishes_sample() {
+        let school = FishSchool::new(vec![3, 4, 3, 1, 2]).unwrap();
+        assert_eq!(school.simulate_fishes(0), Ok(5));
+        assert_eq!(school.simulate_fishes(1), Ok(5));
+        assert_eq!(school.simulate_fishes(2), Ok(6));
+        assert_eq!(school.simulate_fishes(18), Ok(26));
+        assert_eq!(school.simulate_fishes(80), Ok(5934));
+        assert_eq!(school.simulate_fishes(256), Ok(26984457539));
+        assert_eq!(school.day(), 0);
+    }
+
+    #[test]
+    fn test_days_iterator() {
+        let school = FishSchool::new(vec![3, 4, 3, 1, 2]).unwrap();
+        let totals: Vec<usize> = school.days().take(4).map(|state| state.total).collect();
+        assert_eq!(totals, vec![5, 5, 6, 7]);
+
+        let day18 = school.days().nth(18).unwrap();
+        assert_eq!(day18.day, 18);
+        assert_eq!(day18.total, 26);
+        assert_eq!(day18.buckets, vec![3, 5, 3, 2, 2, 1, 5, 1, 4]);
+
+        // Counting in u8 stops before the first day above 255 fish.
+        let last = school.days_as::<u8>().last().unwrap();
+        assert_eq!(
+            last.total as usize,
+            school.simulate_fishes(last.day).unwrap()
+        );
+        assert!(school.simulate_fishes(last.day + 1).unwrap() > 255);
+    }
+
+    #[test]
+    fn test_advance_and_resume() {
+        let mut school = FishSchool::new(vec![3, 4, 3, 1, 2]).unwrap();
+        assert_eq!(school.advance(80), Ok(5934));
+        assert_eq!(school.day(), 80);
+        assert_eq!(school.total(), 5934);
+        // Iterating and simulating start from the current day.
+        assert_eq!(school.days().next().unwrap().day, 80);
+        assert_eq!(school.simulate_fishes(176), Ok(26984457539));
+        assert_eq!(school.advance(176), Ok(26984457539));
+        assert_eq!(school.day(), 256);
+
+        let before = school.clone();
+        assert_eq!(
+            school.advance(100_000),
+            Err(SchoolError::Overflow {
+                day: match school.simulate_fishes(100_000) {
+                    Err(SchoolError::Overflow { day }) => day,
+                    other => panic!("expected an overflow, got {:?}", other),
+                }
+            })
+        );
+        assert_eq!(school, before);
+    }
+
+    #[test]
+    fn test_first_day_reaching() {
+        let school = FishSchool::new(vec![3, 4, 3, 1, 2]).unwrap();
+        for target in 0..300u128 {
+            let expected = school
+                .days()
+                .find(|state| state.total as u128 >= target)
+                .map(|state| state.day);
+            assert_eq!(school.first_day_reaching(target), expected, "{}", target);
+        }
+        assert_eq!(school.first_day_reaching(26), Some(18));
+        assert_eq!(school.first_day_reaching(5934), Some(80));
+        assert_eq!(school.first_day_reaching(5935), Some(81));
+        assert_eq!(school.first_day_reaching(26984457539), Some(256));
+
+        let billion = school.first_day_reaching(1_000_000_000).unwrap();
+        assert!(school.simulate_fishes(billion - 1).unwrap() < 1_000_000_000);
+        assert!(school.simulate_fishes(billion).unwrap() >= 1_000_000_000);
+
+        let target = 10u128.pow(30);
+        let day = school.first_day_reaching(target).unwrap();
+        assert!(school.simulate_fishes_as::<u128>(day - 1).unwrap() < target);
+        assert!(school.simulate_fishes_as::<u128>(day).unwrap() >= target);
+        assert!(school.first_day_reaching(u128::MAX).unwrap() > day);
+
+        let mut resumed = school.clone();
+        resumed.advance(100).unwrap();
+        assert_eq!(resumed.first_day_reaching(5934), Some(100));
+        assert_eq!(resumed.first_day_reaching(target), Some(day));
+
+        let empty = FishSchool::new(vec![]).unwrap();
+        assert_eq!(empty.first_day_reaching(0), Some(0));
+        assert_eq!(empty.first_day_reaching(1), None);
+    }
+
+    #[test]
+    fn test_simulate_fishes_overflow() {
+        let school = FishSchool::new(vec![3, 4, 3, 1, 2]).unwrap();
+        let day = match school.simulate_fishes_as::<u64>(1000) {
+            Err(SchoolError::Overflow { day }) => day,
+            other => panic!("expected an overflow, got {:?}", other),
+        };
+        assert!(day > 256 && day < 1000);
+        assert!(school.simulate_fishes_as::<u64>(day - 1).is_ok());
+        assert!(school.simulate_fishes_as::<u64>(day).is_err());
+        assert_eq!(
+            school.simulate_fishes_as::<u64>(400).unwrap() as u128,
+            school.simulate_fishes_as::<u128>(400).unwrap()
+        );
+        assert!(school.simulate_fishes_as::<u128>(day + 1).is_ok());
+        assert!(school.simulate_fishes(100_000).is_err());
+
+        // The day counter itself is checked; an empty school is not stepped.
+        let empty = FishSchool::new(vec![]).unwrap();
+        assert_eq!(empty.simulate_fishes(usize::MAX), Ok(0));
+        let mut resumed = FishSchool::new(vec![3]).unwrap();
+        resumed.advance(1).unwrap();
+        assert_eq!(
+            resumed.simulate_fishes(usize::MAX),
+            Err(SchoolError::Overflow { day: usize::MAX })
+        );
+
+        // A school that does not fit the count type overflows on its first day.
+        let school = FishSchool::new(vec![3; 300]).unwrap();
+        assert_eq!(
+            school.simulate_fishes_as::<u8>(0),
+            Err(SchoolError::Overflow { day: 0 })
+        );
+        assert_eq!(school.days_as::<u8>().next(), None);
+        assert_eq!(school.simulate_fishes_as::<u16>(0), Ok(300));
+    }
+
+    #[test]
+    fn test_simulate_fishes_big() {
+        let school = FishSchool::new(vec![3, 4, 3, 1, 2]).unwrap();
+        let exact = school.simulate_fishes_as::<u128>(300).unwrap();
+        assert_eq!(
+            school.simulate_fishes_as::<BigUint>(300).unwrap(),
+            BigUint::from(exact)
+        );
+        assert!(school.simulate_fishes_as::<u128>(5000).is_err());
+
+        // Compare with the modular answer.
+        let big = school.simulate_fishes_as::<BigUint>(5000).unwrap();
+        let modulus = 1_000_000_007;
+        let remainder = big
+            .to_string()
+            .bytes()
+            .fold(0, |sum, digit| (sum * 10 + (digit - b'0') as u64) % modulus);
+        assert_eq!(remainder, school.population_mod(5000, modulus));
+    }
+
+    #[test]
+    fn test_population_mod() {
+        let school = FishSchool::new(vec![3, 4, 3, 1, 2]).unwrap();
+        assert_eq!(school.buckets(), vec![0, 1, 1, 2, 1, 0, 0, 0, 0]);
+        assert_eq!(school.population_mod(0, 1_000_000_007), 5);
+        assert_eq!(school.population_mod(18, 1_000_000_007), 26);
+        assert_eq!(school.population_mod(80, 1_000_000_007), 5934);
+        assert_eq!(school.population_mod(256, 1 << 62), 26984457539);
+        assert_eq!(school.population_mod(256, 1000), 539);
+        // Far days only need a logarithmic number of multiplications.
+        // Advancing part of the way first must give the same remainder.
+        let modulus = 1_000_000_007;
+        let far = school.population_mod(1_000_000_000_000, modulus);
+        let mut resumed = school.clone();
+        resumed.advance(200).unwrap();
+        assert_eq!(
+            resumed.population_mod(1_000_000_000_000 - 200, modulus),
+            far
+        );
+        // The totals also follow total(n) = total(n - 7) + total(n - 9).
+        let sum = school.population_mod(1_000_000_000_000 - 7, modulus)
+            + school.population_mod(1_000_000_000_000 - 9, modulus);
+        assert_eq!(sum % modulus, far);
+        assert_eq!(school.population_mod(1_000_000_000_000, 1), 0);
+    }
+}
@@ -0,0 +1,531 @@
+/// This file models lanternfish whose behaviour is left to chance.
+///
+/// In the stochastic model a fish whose timer reaches 0 only spawns with
+/// a given probability, and may die after a maximum number of cycles
+/// instead of restarting its timer. The fish are still counted in
+/// buckets, one row of timers per number of completed cycles, but the
+/// number of fish that spawn from a bucket is drawn at random. Large
+/// buckets are drawn from an approximation of the binomial distribution,
+/// so the cost of a day does not grow with the population.
+///
+/// The random numbers come from a seeded generator, so a run can be
+/// reproduced, and `monte_carlo` repeats runs to summarize the spread
+/// of the population on a given day.
+use std::fmt;
+
+use crate::count::Count;
+use crate::school::{FishSchool, Lifecycle, SchoolError};
+
+/// A small xorshift64* pseudo-random generator.
+/// It is not suitable for cryptography, only for reproducible simulations.
+#[derive(Debug, Clone)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    /// Creates a generator from a seed. Every seed, including 0, is valid.
+    pub fn new(seed: u64) -> Self {
+        // Scramble the seed with splitmix64 so that close seeds give
+        // unrelated sequences and the state is never 0.
+        let mut z = seed.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^= z >> 31;
+        Rng { state: z.max(1) }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    /// Returns a number uniformly distributed in `0.0..1.0`.
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Returns a standard normal number, with the Box-Muller transform.
+    fn next_normal(&mut self) -> f64 {
+        let radius = (-2.0 * (1.0 - self.next_f64()).ln()).sqrt();
+        radius * (2.0 * std::f64::consts::PI * self.next_f64()).cos()
+    }
+
+    /// Returns a Poisson distributed number with the given mean, by
+    /// multiplying uniform numbers until they drop below `e^-mean`.
+    fn poisson(&mut self, mean: f64) -> usize {
+        let limit = (-mean).exp();
+        let mut product = self.next_f64();
+        let mut count = 0;
+        while product > limit {
+            product *= self.next_f64();
+            count += 1;
+        }
+        count
+    }
+
+    /// Returns how many of `trials` events with the given probability happen.
+    ///
+    /// Up to EXACT_TRIALS trials are drawn one by one. Beyond that, the
+    /// count of the less likely outcome is drawn from a Poisson
+    /// distribution if it averages fewer than POISSON_MEAN events, and
+    /// from a normal distribution otherwise, so a draw takes constant time.
+    fn binomial(&mut self, trials: usize, probability: f64) -> usize {
+        if probability <= 0.0 {
+            return 0;
+        }
+        if probability >= 1.0 {
+            return trials;
+        }
+        if trials <= EXACT_TRIALS {
+            return (0..trials)
+                .filter(|_| self.next_f64() < probability)
+                .count();
+        }
+        let rare = probability.min(1.0 - probability);
+        let mean = trials as f64 * rare;
+        let count = if mean < POISSON_MEAN {
+            self.poisson(mean).min(trials)
+        } else {
+            let deviation = (mean * (1.0 - rare)).sqrt();
+            (mean + deviation * self.next_normal())
+                .round()
+                .clamp(0.0, trials as f64) as usize
+        };
+        if rare == probability {
+            count
+        } else {
+            trials - count
+        }
+    }
+}
+
+/// The number of trials up to which `Rng::binomial` draws every trial.
+const EXACT_TRIALS: usize = 64;
+
+/// The mean below which `Rng::binomial` approximates with a Poisson
+/// distribution rather than a normal one.
+const POISSON_MEAN: f64 = 20.0;
+
+/// The parameters of the stochastic model.
+/// The default model always spawns and never dies, like `FishSchool`.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct StochasticModel {
+    /// The probability that a fish whose timer reaches 0 spawns a new fish.
+    pub spawn_probability: f64,
+    /// The number of spawning cycles after which a fish dies, or None
+    /// if fish live forever. At most MAX_CYCLES, since the school keeps
+    /// a row of buckets per cycle.
+    pub max_cycles: Option<usize>,
+}
+
+/// The highest `max_cycles` of a StochasticModel.
+pub const MAX_CYCLES: usize = 10_000;
+
+impl Default for StochasticModel {
+    fn default() -> Self {
+        StochasticModel {
+            spawn_probability: 1.0,
+            max_cycles: None,
+        }
+    }
+}
+
+impl StochasticModel {
+    fn validate(&self) -> Result<(), SchoolError> {
+        if !(0.0..=1.0).contains(&self.spawn_probability) {
+            return Err(SchoolError::InvalidModel(
+                "the spawn probability must be between 0 and 1",
+            ));
+        }
+        if self.max_cycles == Some(0) {
+            return Err(SchoolError::InvalidModel(
+                "fish must live at least one cycle",
+            ));
+        }
+        if self.max_cycles > Some(MAX_CYCLES) {
+            return Err(SchoolError::InvalidModel(
+                "fish can live at most MAX_CYCLES cycles, use no maximum instead",
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// A school of fish following a StochasticModel.
+#[derive(Debug, Clone)]
+pub struct StochasticSchool {
+    lifecycle: Lifecycle,
+    model: StochasticModel,
+    /// `buckets[cycles][timer]` is the number of fish with the timer
+    /// that have completed `cycles` cycles. Without a maximum number
+    /// of cycles the cycles do not matter, and there is a single row.
+    buckets: Vec<Vec<usize>>,
+    /// The buckets of the next day while stepping, kept to reuse them.
+    next: Vec<Vec<usize>>,
+    day: usize,
+    rng: Rng,
+}
+
+impl StochasticSchool {
+    /// Starts a stochastic simulation from the current state of a school.
+    /// All of its fish are considered to have completed no cycle.
+    pub fn new(
+        school: &FishSchool,
+        model: StochasticModel,
+        seed: u64,
+    ) -> Result<Self, SchoolError> {
+        model.validate()?;
+        let lifecycle = school.lifecycle();
+        let rows = model.max_cycles.unwrap_or(1);
+        let mut buckets = vec![vec![0; lifecycle.max_timer() + 1]; rows];
+        buckets[0] = school.buckets().to_vec();
+        Ok(StochasticSchool {
+            lifecycle,
+            model,
+            next: buckets.clone(),
+            buckets,
+            day: school.day(),
+            rng: Rng::new(seed),
+        })
+    }
+
+    pub fn day(&self) -> usize {
+        self.day
+    }
+
+    /// Returns the number of fish for each timer, whatever their age.
+    pub fn buckets(&self) -> Vec<usize> {
+        let mut buckets = vec![0; self.lifecycle.max_timer() + 1];
+        for row in &self.buckets {
+            for (timer, count) in row.iter().enumerate() {
+                buckets[timer] += count;
+            }
+        }
+        buckets
+    }
+
+    pub fn total(&self) -> usize {
+        self.buckets.iter().flatten().sum()
+    }
+
+    /// Advances the school by one day.
+    /// On overflow the school, including its generator, is left unchanged,
+    /// so stepping again draws the same numbers.
+    pub fn step(&mut self) -> Result<(), SchoolError> {
+        let Lifecycle {
+            reset_timer,
+            newborn_timer,
+        } = self.lifecycle;
+        let overflow = SchoolError::Overflow { day: self.day + 1 };
+        let add = |into: &mut usize, count: usize| -> Result<(), SchoolError> {
+            *into = Count::checked_add(into, &count).ok_or(overflow.clone())?;
+            Ok(())
+        };
+        let size = self.lifecycle.max_timer() + 1;
+        let mut rng = self.rng.clone();
+        let next = &mut self.next;
+        next.iter_mut().for_each(|row| row.fill(0));
+        for (cycles, row) in self.buckets.iter().enumerate() {
+            for timer in 1..size {
+                add(&mut next[cycles][timer - 1], row[timer])?;
+            }
+            let spawning = row[0];
+            let born = rng.binomial(spawning, self.model.spawn_probability);
+            add(&mut next[0][newborn_timer], born)?;
+            match self.model.max_cycles {
+                // The fish die after spawning for the last time.
+                Some(max_cycles) if cycles + 1 >= max_cycles => {}
+                Some(_) => add(&mut next[cycles + 1][reset_timer], spawning)?,
+                None => add(&mut next[0][reset_timer], spawning)?,
+            }
+        }
+        // Keep the total within a usize, so that `total` cannot overflow.
+        next.iter()
+            .flatten()
+            .try_fold(0usize, |sum, &count| sum.checked_add(count))
+            .ok_or(overflow)?;
+        std::mem::swap(&mut self.buckets, &mut self.next);
+        self.rng = rng;
+        self.day += 1;
+        Ok(())
+    }
+
+    /// Advances the school by a certain number of days and returns the
+    /// number of fish on the new day, or the first day whose population
+    /// does not fit in a usize. The school is left on the last day that fits.
+    pub fn advance(&mut self, days: usize) -> Result<usize, SchoolError> {
+        for _ in 0..days {
+            self.step()?;
+        }
+        Ok(self.total())
+    }
+}
+
+/// The populations reached by the runs of `monte_carlo`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Summary {
+    /// The population of every run, sorted.
+    totals: Vec<usize>,
+}
+
+impl Summary {
+    pub fn runs(&self) -> usize {
+        self.totals.len()
+    }
+
+    pub fn mean(&self) -> f64 {
+        self.totals.iter().map(|&total| total as f64).sum::<f64>() / self.totals.len() as f64
+    }
+
+    /// Returns the smallest population that at least `percent` percent
+    /// of the runs did not exceed (the nearest-rank percentile).
+    ///
+    /// Panics if `percent` is not between 0 and 100.
+    pub fn percentile(&self, percent: f64) -> usize {
+        assert!((0.0..=100.0).contains(&percent), "percent out of range");
+        let rank = (percent / 100.0 * self.totals.len() as f64).ceil() as usize;
+        self.totals[rank.max(1) - 1]
+    }
+
+    pub fn min(&self) -> usize {
+        self.totals[0]
+    }
+
+    pub fn max(&self) -> usize {
+        self.totals[self.totals.len() - 1]
+    }
+}
+
+impl fmt::Display for Summary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "runs: {}, mean: {:.1}, min: {}, p5: {}, p50: {}, p95: {}, max: {}",
+            self.runs(),
+            self.mean(),
+            self.min(),
+            self.percentile(5.0),
+            self.percentile(50.0),
+            self.percentile(95.0),
+            self.max()
+        )
+    }
+}
+
+/// Runs the stochastic model `runs` times for `days` days from the
+/// current state of the school and summarizes the populations reached,
+/// or returns the first overflow of a run.
+/// Run `n` uses a generator seeded from `seed + n`, so the summary only
+/// depends on the arguments.
+///
+/// Panics if `runs` is 0.
+pub fn monte_carlo(
+    school: &FishSchool,
+    model: StochasticModel,
+    days: usize,
+    runs: usize,
+    seed: u64,
+) -> Result<Summary, SchoolError> {
+    assert!(runs > 0, "at least one run is needed");
+    let mut totals = (0..runs as u64)
+        .map(|run| {
+            StochasticSchool::new(school, model, seed.wrapping_add(run))
+                .and_then(|mut school| school.advance(days))
+        })
+        .collect::<Result<Vec<usize>, SchoolError>>()?;
+    totals.sort_unstable();
+    Ok(Summary { totals })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> FishSchool {
+        FishSchool::new(vec![3, 4, 3, 1, 2]).unwrap()
+    }
+
+    #[test]
+    fn test_rng_is_reproducible() {
+        let first: Vec<u64> = (0..5)
+            .scan(Rng::new(42), |rng, _| Some(rng.next_u64()))
+            .collect();
+        let again: Vec<u64> = (0..5)
+            .scan(Rng::new(42), |rng, _| Some(rng.next_u64()))
+            .collect();
+        let other: Vec<u64> = (0..5)
+            .scan(Rng::new(43), |rng, _| Some(rng.next_u64()))
+            .collect();
+        assert_eq!(first, again);
+        assert_ne!(first, other);
+
+        let mut rng = Rng::new(0);
+        let mean = (0..10_000).map(|_| rng.next_f64()).sum::<f64>() / 10_000.0;
+        assert!((mean - 0.5).abs() < 0.02);
+    }
+
+    #[test]
+    fn test_binomial() {
+        let mut rng = Rng::new(3);
+        assert_eq!(rng.binomial(1000, 0.0), 0);
+        assert_eq!(rng.binomial(1000, 1.0), 1000);
+        // Exact, Poisson and normal draws, for both tails.
+        for (trials, probability) in [
+            (50, 0.3),
+            (10_000, 0.001),
+            (10_000, 0.999),
+            (10_000, 0.3),
+            (1 << 60, 0.5),
+        ] {
+            let mean = trials as f64 * probability;
+            let deviation = (mean * (1.0 - probability)).sqrt();
+            let draws: Vec<usize> = (0..2000)
+                .map(|_| rng.binomial(trials, probability))
+                .collect();
+            assert!(draws.iter().all(|&draw| draw <= trials));
+            let average = draws.iter().map(|&draw| draw as f64).sum::<f64>() / 2000.0;
+            assert!(
+                (average - mean).abs() < 4.0 * deviation / 2000f64.sqrt(),
+                "{} trials with probability {}: average {}",
+                trials,
+                probability,
+                average
+            );
+        }
+    }
+
+    #[test]
+    fn test_default_model_is_deterministic() {
+        let mut school = StochasticSchool::new(&sample(), StochasticModel::default(), 7).unwrap();
+        assert_eq!(school.advance(18), Ok(26));
+        assert_eq!(school.advance(62), Ok(5934));
+        assert_eq!(school.day(), 80);
+        assert_eq!(school.buckets(), sample().days().nth(80).unwrap().buckets);
+
+        // Like FishSchool, the school stops on the last day that fits.
+        let overflow = sample().simulate_fishes(500).unwrap_err();
+        let mut school = StochasticSchool::new(&sample(), StochasticModel::default(), 7).unwrap();
+        assert_eq!(school.advance(500), Err(overflow.clone()));
+        let SchoolError::Overflow { day } = overflow else {
+            panic!("expected an overflow, got {:?}", overflow);
+        };
+        assert_eq!(school.day(), day - 1);
+        assert_eq!(Ok(school.total()), sample().simulate_fishes(day - 1));
+        assert_eq!(
+            monte_carlo(&sample(), StochasticModel::default(), 500, 2, 0),
+            Err(SchoolError::Overflow { day })
+        );
+    }
+
+    #[test]
+    fn test_overflow_is_reproducible() {
+        let model = StochasticModel {
+            spawn_probability: 0.9,
+            max_cycles: None,
+        };
+        let mut school = StochasticSchool::new(&sample(), model, 5).unwrap();
+        let Err(SchoolError::Overflow { day }) = school.advance(2000) else {
+            panic!("expected an overflow");
+        };
+        assert_eq!(school.day(), day - 1);
+        // A failed step does not draw, so the run still matches its seed.
+        let before = school.buckets();
+        assert_eq!(school.step(), Err(SchoolError::Overflow { day }));
+        assert_eq!(school.buckets(), before);
+        let mut replay = StochasticSchool::new(&sample(), model, 5).unwrap();
+        replay.advance(day - 1).unwrap();
+        assert_eq!(replay.buckets(), before);
+        assert_eq!(replay.rng.next_u64(), school.rng.next_u64());
+    }
+
+    #[test]
+    fn test_mortality() {
+        // A single fish spawning every 7 days and dying after its second spawn.
+        let school = FishSchool::new(vec![0]).unwrap();
+        let model = StochasticModel {
+            spawn_probability: 1.0,
+            max_cycles: Some(2),
+        };
+        let mut stochastic = StochasticSchool::new(&school, model, 0).unwrap();
+        assert_eq!(stochastic.advance(1), Ok(2));
+        // On day 8 the fish spawns for the second time and dies.
+        assert_eq!(stochastic.advance(7), Ok(2));
+        // On day 10 its first child, born on day 1, spawns.
+        assert_eq!(stochastic.advance(2), Ok(3));
+        assert_eq!(stochastic.buckets(), vec![0, 0, 0, 0, 0, 0, 2, 0, 1]);
+
+        let immortal = StochasticModel {
+            max_cycles: None,
+            ..model
+        };
+        assert!(
+            StochasticSchool::new(&school, immortal, 0)
+                .unwrap()
+                .advance(40)
+                .unwrap()
+                > StochasticSchool::new(&school, model, 0)
+                    .unwrap()
+                    .advance(40)
+                    .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_invalid_model() {
+        for model in [
+            StochasticModel {
+                spawn_probability: 1.5,
+                max_cycles: None,
+            },
+            StochasticModel {
+                spawn_probability: 0.5,
+                max_cycles: Some(0),
+            },
+            StochasticModel {
+                spawn_probability: 0.5,
+                max_cycles: Some(usize::MAX),
+            },
+        ] {
+            assert!(matches!(
+                StochasticSchool::new(&sample(), model, 0),
+                Err(SchoolError::InvalidModel(_))
+            ));
+        }
+        let oldest = StochasticModel {
+            spawn_probability: 0.5,
+            max_cycles: Some(MAX_CYCLES),
+        };
+        assert!(StochasticSchool::new(&sample(), oldest, 0).is_ok());
+    }
+
+    #[test]
+    fn test_monte_carlo() {
+        let model = StochasticModel {
+            spawn_probability: 0.5,
+            max_cycles: Some(3),
+        };
+        let summary = monte_carlo(&sample(), model, 40, 200, 1).unwrap();
+        assert_eq!(summary, monte_carlo(&sample(), model, 40, 200, 1).unwrap());
+        assert_eq!(summary.runs(), 200);
+        assert!(summary.min() <= summary.percentile(5.0));
+        assert!(summary.percentile(5.0) <= summary.percentile(50.0));
+        assert!(summary.percentile(50.0) <= summary.percentile(95.0));
+        assert!(summary.percentile(95.0) <= summary.max());
+        assert!(summary.min() < summary.max());
+        assert!(summary.mean() < sample().simulate_fishes(40).unwrap() as f64);
+
+        // Large populations are drawn in constant time per bucket.
+        let large = monte_carlo(&sample(), model, 400, 20, 1).unwrap();
+        assert!(large.min() > 0);
+
+        let certain = monte_carlo(&sample(), StochasticModel::default(), 80, 3, 1).unwrap();
+        assert_eq!(certain.mean(), 5934.0);
+        assert_eq!(
+            certain.to_string(),
+            "runs: 3, mean: 5934.0, min: 5934, p5: 5934, p50: 5934, p95: 5934, max: 5934"
+        );
+    }
+}